@@ -0,0 +1,30 @@
+struct BubbleMaterial {
+    color: vec4<f32>,
+    shine: vec4<f32>,
+    outline: vec4<f32>,
+    outline_width: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: BubbleMaterial;
+
+struct FragmentInput {
+    #import bevy_sprite::mesh2d_vertex_output
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    // Circle uvs span 0..1 across the bubble, so the centre is at 0.5.
+    let rim = length(in.uv - vec2<f32>(0.5, 0.5)) * 2.0;
+    var color = material.color.rgb * mix(1.0, 0.6, rim * rim);
+
+    // Soft highlight towards the top left.
+    let highlight = 1.0 - smoothstep(0.0, 0.3, length(in.uv - vec2<f32>(0.35, 0.3)));
+    color = mix(color, material.shine.rgb, highlight * material.shine.a);
+
+    if (rim > 1.0 - material.outline_width) {
+        color = mix(color, material.outline.rgb, material.outline.a);
+    }
+
+    return vec4<f32>(color, material.color.a);
+}
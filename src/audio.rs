@@ -0,0 +1,278 @@
+use crate::game::events::{Collider, CollisionEvent, HookFiredEvent, LevelClearedEvent};
+use crate::AppState;
+use bevy::audio::AudioSink;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct SoundPlugin;
+
+const BOUNCE_SOUND_FILE: &str = "sounds/bounce.wav";
+const POP_SOUND_FILE: &str = "sounds/pop.wav";
+const HOOK_SOUND_FILE: &str = "sounds/hook.wav";
+const CEILING_SOUND_FILE: &str = "sounds/ceiling.wav";
+const REWARD_SOUND_FILE: &str = "sounds/reward.wav";
+const DEATH_SOUND_FILE: &str = "sounds/death.wav";
+const CLEAR_SOUND_FILE: &str = "sounds/clear.wav";
+const MENU_MUSIC_FILE: &str = "sounds/menu_music.wav";
+const GAME_MUSIC_FILE: &str = "sounds/game_music.wav";
+
+// Bounces happen all the time, keep them in the background.
+const BOUNCE_VOLUME: f32 = 0.3;
+// Pop pitch is this divided by the bubble size, so small bubbles pop higher.
+const POP_PITCH: f32 = 3.;
+
+const MASTER_VOLUME: f32 = 1.;
+const MUSIC_VOLUME: f32 = 0.4;
+const EFFECTS_VOLUME: f32 = 0.8;
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Resource)]
+struct Sounds {
+    bounce: Handle<AudioSource>,
+    pop: Handle<AudioSource>,
+    hook: Handle<AudioSource>,
+    ceiling: Handle<AudioSource>,
+    reward: Handle<AudioSource>,
+    death: Handle<AudioSource>,
+    clear: Handle<AudioSource>,
+    menu_music: Handle<AudioSource>,
+    game_music: Handle<AudioSource>,
+}
+
+// Volume buses, the effective volume of a bus is scaled by the master volume.
+#[derive(Resource, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct VolumeSettings {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self {
+            master: MASTER_VOLUME,
+            music: MUSIC_VOLUME,
+            effects: EFFECTS_VOLUME,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum VolumeBus {
+    Master,
+    Music,
+    Effects,
+}
+
+impl VolumeSettings {
+    pub fn volume(&self, bus: VolumeBus) -> f32 {
+        match bus {
+            VolumeBus::Master => self.master,
+            VolumeBus::Music => self.music,
+            VolumeBus::Effects => self.effects,
+        }
+    }
+
+    fn volume_mut(&mut self, bus: VolumeBus) -> &mut f32 {
+        match bus {
+            VolumeBus::Master => &mut self.master,
+            VolumeBus::Music => &mut self.music,
+            VolumeBus::Effects => &mut self.effects,
+        }
+    }
+
+    // Steps are rounded so the volumes stay on whole steps.
+    fn step(&mut self, bus: VolumeBus, steps: f32) {
+        let volume = self.volume_mut(bus);
+        *volume = ((*volume / VOLUME_STEP).round() + steps) * VOLUME_STEP;
+        *volume = volume.clamp(0., 1.);
+    }
+
+    pub fn quieter(&mut self, bus: VolumeBus) {
+        self.step(bus, -1.);
+    }
+
+    pub fn louder(&mut self, bus: VolumeBus) {
+        self.step(bus, 1.);
+    }
+
+    pub fn clamp(&mut self) {
+        for bus in [VolumeBus::Master, VolumeBus::Music, VolumeBus::Effects] {
+            let volume = self.volume_mut(bus);
+            *volume = volume.clamp(0., 1.);
+        }
+    }
+
+    fn music(&self) -> f32 {
+        self.master * self.music
+    }
+
+    fn effects(&self) -> f32 {
+        self.master * self.effects
+    }
+}
+
+#[derive(Resource, Default)]
+struct Music {
+    sink: Option<Handle<AudioSink>>,
+}
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Music::default())
+            .add_startup_system(sounds_system)
+            .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(menu_music_system))
+            .add_system_set(SystemSet::on_exit(AppState::Menu).with_system(stop_music_system))
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(game_music_system))
+            .add_system_set(SystemSet::on_exit(AppState::Game).with_system(stop_music_system))
+            .add_system(collision_sound_system)
+            .add_system(hook_sound_system)
+            .add_system(clear_sound_system)
+            .add_system(music_volume_system);
+    }
+}
+
+fn sounds_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Sounds {
+        bounce: asset_server.load(BOUNCE_SOUND_FILE),
+        pop: asset_server.load(POP_SOUND_FILE),
+        hook: asset_server.load(HOOK_SOUND_FILE),
+        ceiling: asset_server.load(CEILING_SOUND_FILE),
+        reward: asset_server.load(REWARD_SOUND_FILE),
+        death: asset_server.load(DEATH_SOUND_FILE),
+        clear: asset_server.load(CLEAR_SOUND_FILE),
+        menu_music: asset_server.load(MENU_MUSIC_FILE),
+        game_music: asset_server.load(GAME_MUSIC_FILE),
+    });
+}
+
+fn play_music(
+    track: &Handle<AudioSource>,
+    audio: &Audio,
+    audio_sinks: &Assets<AudioSink>,
+    music: &mut Music,
+    volume: &VolumeSettings,
+) {
+    let sink = audio.play_with_settings(
+        track.clone(),
+        PlaybackSettings::LOOP.with_volume(volume.music()),
+    );
+    music.sink = Some(audio_sinks.get_handle(sink));
+}
+
+fn menu_music_system(
+    sounds: Res<Sounds>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut music: ResMut<Music>,
+    volume: Res<VolumeSettings>,
+) {
+    play_music(
+        &sounds.menu_music,
+        &audio,
+        &audio_sinks,
+        &mut music,
+        &volume,
+    );
+}
+
+fn game_music_system(
+    sounds: Res<Sounds>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    mut music: ResMut<Music>,
+    volume: Res<VolumeSettings>,
+) {
+    play_music(
+        &sounds.game_music,
+        &audio,
+        &audio_sinks,
+        &mut music,
+        &volume,
+    );
+}
+
+fn stop_music_system(audio_sinks: Res<Assets<AudioSink>>, mut music: ResMut<Music>) {
+    if let Some(sink) = music.sink.take() {
+        if let Some(sink) = audio_sinks.get(&sink) {
+            sink.stop();
+        }
+    }
+}
+
+fn music_volume_system(
+    volume: Res<VolumeSettings>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    music: Res<Music>,
+) {
+    if volume.is_changed() {
+        if let Some(sink) = music.sink.as_ref().and_then(|sink| audio_sinks.get(sink)) {
+            sink.set_volume(volume.music());
+        }
+    }
+}
+
+fn collision_sound_system(
+    mut collision_events: EventReader<CollisionEvent>,
+    sounds: Res<Sounds>,
+    audio: Res<Audio>,
+    volume: Res<VolumeSettings>,
+) {
+    for collision in collision_events.iter() {
+        let (sound, settings) = match (collision.first, collision.second) {
+            (Collider::Bubble { .. }, Collider::Wall) => (
+                &sounds.bounce,
+                PlaybackSettings::ONCE.with_volume(volume.effects() * BOUNCE_VOLUME),
+            ),
+            (Collider::Bubble { size }, Collider::Hook) => (
+                &sounds.pop,
+                PlaybackSettings::ONCE
+                    .with_volume(volume.effects())
+                    .with_speed(POP_PITCH / size),
+            ),
+            (Collider::Hook, Collider::Ceiling) => (
+                &sounds.ceiling,
+                PlaybackSettings::ONCE.with_volume(volume.effects()),
+            ),
+            (Collider::Player, Collider::Bubble { .. }) => (
+                &sounds.death,
+                PlaybackSettings::ONCE.with_volume(volume.effects()),
+            ),
+            (Collider::Player, Collider::Reward | Collider::PowerUp) => (
+                &sounds.reward,
+                PlaybackSettings::ONCE.with_volume(volume.effects()),
+            ),
+            _ => continue,
+        };
+        audio.play_with_settings(sound.clone(), settings);
+    }
+}
+
+fn hook_sound_system(
+    mut hook_fired_events: EventReader<HookFiredEvent>,
+    sounds: Res<Sounds>,
+    audio: Res<Audio>,
+    volume: Res<VolumeSettings>,
+) {
+    for _ in hook_fired_events.iter() {
+        audio.play_with_settings(
+            sounds.hook.clone(),
+            PlaybackSettings::ONCE.with_volume(volume.effects()),
+        );
+    }
+}
+
+fn clear_sound_system(
+    mut level_cleared_events: EventReader<LevelClearedEvent>,
+    sounds: Res<Sounds>,
+    audio: Res<Audio>,
+    volume: Res<VolumeSettings>,
+) {
+    for _ in level_cleared_events.iter() {
+        audio.play_with_settings(
+            sounds.clear.clone(),
+            PlaybackSettings::ONCE.with_volume(volume.effects()),
+        );
+    }
+}
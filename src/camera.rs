@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::window::WindowResized;
+
+pub struct CameraPlugin;
+
+// Area that always stays visible: the arena with a band above it for the HUD.
+pub const VIEW_WIDTH: f32 = 1160.;
+pub const VIEW_HEIGHT: f32 = 960.;
+
+const LETTERBOX_COLOR: Color = Color::BLACK;
+// Above everything in the world, the UI is still drawn on top.
+const LETTERBOX_Z: f32 = 100.;
+// Big enough to cover whatever is visible outside the view on any aspect ratio.
+const LETTERBOX_SIZE: f32 = 100_000.;
+
+#[derive(Component)]
+pub struct MainCamera;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(camera_system)
+            .add_startup_system(letterbox_system)
+            .add_startup_system(ui_scale_system)
+            .add_system(window_resized_system);
+    }
+}
+
+fn camera_system(mut commands: Commands) {
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::Auto {
+        min_width: VIEW_WIDTH,
+        min_height: VIEW_HEIGHT,
+    };
+    commands.spawn((camera, MainCamera));
+}
+
+// Bars covering the part of the window outside the view.
+fn letterbox_system(mut commands: Commands) {
+    let offset_x = (VIEW_WIDTH + LETTERBOX_SIZE) / 2.;
+    let offset_y = (VIEW_HEIGHT + LETTERBOX_SIZE) / 2.;
+    for position in [
+        Vec2::new(-offset_x, 0.),
+        Vec2::new(offset_x, 0.),
+        Vec2::new(0., -offset_y),
+        Vec2::new(0., offset_y),
+    ] {
+        commands.spawn(SpriteBundle {
+            transform: Transform {
+                translation: position.extend(LETTERBOX_Z),
+                scale: Vec3::new(LETTERBOX_SIZE, LETTERBOX_SIZE, 1.),
+                ..default()
+            },
+            sprite: Sprite {
+                color: LETTERBOX_COLOR,
+                ..default()
+            },
+            ..default()
+        });
+    }
+}
+
+// Scales the UI together with the view, so one UI pixel is one world unit.
+fn ui_scale(width: f32, height: f32) -> f64 {
+    (width / VIEW_WIDTH).min(height / VIEW_HEIGHT) as f64
+}
+
+fn ui_scale_system(windows: Res<Windows>, mut ui_scale_resource: ResMut<UiScale>) {
+    if let Some(window) = windows.get_primary() {
+        ui_scale_resource.scale = ui_scale(window.width(), window.height());
+    }
+}
+
+fn window_resized_system(
+    mut resized_events: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut ui_scale_resource: ResMut<UiScale>,
+) {
+    let primary_id = match windows.get_primary() {
+        Some(window) => window.id(),
+        None => return,
+    };
+    for resized in resized_events.iter().filter(|event| event.id == primary_id) {
+        ui_scale_resource.scale = ui_scale(resized.width, resized.height);
+    }
+}
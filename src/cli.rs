@@ -0,0 +1,178 @@
+use crate::GameMode;
+use bevy::prelude::*;
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+
+const USAGE: &str = "Usage: project-bubblerustle [OPTIONS]
+
+Options:
+  --skip-splash         Start on the menu
+  --mode <MODE>         Start a game of campaign, survival, panic or time-attack
+  --level <N>           Start a game on the Nth level of the campaign
+  --seed <SEED>         Seed every game with SEED instead of a random number
+  --windowed            Run in a window
+  --fullscreen          Run in fullscreen
+  --resolution <WxH>    Size of the window, like 1280x720
+  --replay <FILE>       Watch the replay saved in FILE
+  --headless            Run without a window as fast as possible, implies a game
+  --ticks <N>           End the game after N ticks
+  --dump <FILE>         Write the state of the game to FILE once it ends, - for the output
+  -h, --help            Print this help";
+
+// Options the game was started with, see `USAGE`.
+#[derive(Resource, Default)]
+pub struct Cli {
+    pub skip_splash: bool,
+    pub mode: Option<GameMode>,
+    // Index of the campaign level, counted from one on the command line.
+    pub level: Option<usize>,
+    pub seed: Option<u64>,
+    pub window_mode: Option<WindowMode>,
+    pub resolution: Option<(f32, f32)>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    pub ticks: Option<usize>,
+    pub dump: Option<PathBuf>,
+}
+
+impl Cli {
+    // Prints the usage and exits on invalid arguments.
+    pub fn parse() -> Self {
+        match Self::parse_args(std::env::args().skip(1)) {
+            Ok(cli) => cli,
+            Err(error) => {
+                eprintln!("{}\n\n{}", error, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut cli = Self::default();
+        while let Some(option) = args.next() {
+            match option.as_str() {
+                "--skip-splash" => cli.skip_splash = true,
+                "--mode" => cli.mode = Some(parse_mode(&value(&mut args, &option)?)?),
+                "--level" => {
+                    let level: usize = parse_number(&value(&mut args, &option)?, &option)?;
+                    cli.level = Some(level.checked_sub(1).ok_or("Levels are counted from 1")?);
+                }
+                "--seed" => cli.seed = Some(parse_number(&value(&mut args, &option)?, &option)?),
+                "--windowed" => cli.window_mode = Some(WindowMode::Windowed),
+                "--fullscreen" => cli.window_mode = Some(WindowMode::BorderlessFullscreen),
+                "--resolution" => {
+                    let resolution = value(&mut args, &option)?;
+                    let (width, height) = resolution
+                        .split_once('x')
+                        .ok_or_else(|| format!("Invalid resolution {}", resolution))?;
+                    cli.resolution = Some((
+                        parse_number(width, &option)?,
+                        parse_number(height, &option)?,
+                    ));
+                }
+                "--replay" => cli.replay = Some(value(&mut args, &option)?.into()),
+                "--headless" => cli.headless = true,
+                "--ticks" => cli.ticks = Some(parse_number(&value(&mut args, &option)?, &option)?),
+                "--dump" => cli.dump = Some(value(&mut args, &option)?.into()),
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ => return Err(format!("Unknown option {}", option)),
+            }
+        }
+        Ok(cli)
+    }
+
+    // Whether the game is started right away, skipping the splash and the menu.
+    pub fn starts_game(&self) -> bool {
+        self.mode.is_some() || self.level.is_some() || self.replay.is_some() || self.headless
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing the value of {}", option))
+}
+
+fn parse_number<T: FromStr>(value: &str, option: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {} of {}", value, option))
+}
+
+fn parse_mode(mode: &str) -> Result<GameMode, String> {
+    match mode {
+        "campaign" => Ok(GameMode::Campaign),
+        "survival" => Ok(GameMode::Survival),
+        "panic" => Ok(GameMode::Panic),
+        "time-attack" => Ok(GameMode::TimeAttack),
+        _ => Err(format!("Unknown mode {}", mode)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, String> {
+        Cli::parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_options_open_the_splash() {
+        let cli = parse(&[]).unwrap();
+        assert!(!cli.starts_game());
+        assert!(!cli.headless);
+        assert_eq!(cli.mode, None);
+    }
+
+    #[test]
+    fn options_take_their_values() {
+        let cli = parse(&[
+            "--mode",
+            "time-attack",
+            "--level",
+            "3",
+            "--seed",
+            "42",
+            "--fullscreen",
+            "--resolution",
+            "1280x720",
+            "--headless",
+            "--ticks",
+            "600",
+            "--dump",
+            "-",
+        ])
+        .unwrap();
+        assert_eq!(cli.mode, Some(GameMode::TimeAttack));
+        assert_eq!(cli.level, Some(2));
+        assert_eq!(cli.seed, Some(42));
+        assert_eq!(cli.window_mode, Some(WindowMode::BorderlessFullscreen));
+        assert_eq!(cli.resolution, Some((1280., 720.)));
+        assert!(cli.headless);
+        assert_eq!(cli.ticks, Some(600));
+        assert_eq!(cli.dump, Some(PathBuf::from("-")));
+        assert!(cli.starts_game());
+    }
+
+    #[test]
+    fn a_replay_starts_the_game() {
+        let cli = parse(&["--replay", "replays/1.replay.ron"]).unwrap();
+        assert_eq!(cli.replay, Some(PathBuf::from("replays/1.replay.ron")));
+        assert!(cli.starts_game());
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        assert!(parse(&["--jump"]).is_err());
+        assert!(parse(&["--mode", "arcade"]).is_err());
+        assert!(parse(&["--mode"]).is_err());
+        assert!(parse(&["--level", "0"]).is_err());
+        assert!(parse(&["--seed", "-1"]).is_err());
+        assert!(parse(&["--resolution", "1280"]).is_err());
+        assert!(parse(&["--resolution", "widex720"]).is_err());
+    }
+}
@@ -1,24 +1,74 @@
-use crate::{despawn_screen, AppState, Scores};
+use crate::records::{LevelRecord, Records};
+use crate::settings::Accessibility;
+use crate::{despawn_screen, AppState, Fonts, GameMode, Scores};
+use animation::{AnimationPlugin, PlayerAnimations, PlayerAtlas};
+use bevy::ecs::schedule::ShouldRun;
 use bevy::prelude::*;
 use bevy::sprite::collide_aabb::{collide, Collision};
-use bevy::sprite::MaterialMesh2dBundle;
 use board::BoardPlugin;
 use bubble::BubblePlugin;
-use components::{Bubble, BubbleSize, GameScreen, Hook, Movement, Player, Reward, Wall};
+use combo::{spawn_floating_text, Combo, ComboPlugin};
+use components::{
+    Breakable, Bubble, BubbleKind, BubbleSize, Ceiling, GameScreen, Glass, Hook, Movement, Player,
+    Reward, Wall,
+};
+use config::{ConfigLabel, ConfigPlugin, GameConfig};
+use console::ConsolePlugin;
+use debug::DebugPlugin;
+use editor::EditorPlugin;
+use events::{
+    BubblePoppedEvent, Collider, CollisionEvent, EventsPlugin, GameOverEvent, HookMissedEvent,
+    LevelClearedEvent, PlayerHitEvent, RewardCollectedEvent,
+};
+use ghost::GhostPlugin;
+use hud::HudPlugin;
+use input::InputPlugin;
+use juice::JuicePlugin;
+use launch::LaunchPlugin;
+use level::{Level, LevelHandles, LevelPlugin};
+use panic::PanicPlugin;
+use particles::ParticlesPlugin;
+use platform::PlatformPlugin;
 use player::PlayerPlugin;
+use powerup::PowerUpPlugin;
+use rand::rngs::StdRng;
+use replay::{ReplayPlayback, ReplayPlugin};
 use std::collections::HashSet;
+use survival::SurvivalPlugin;
+use theme::ThemePlugin;
 
-use self::components::{LivesText, RewardScore, ScoreText};
+use self::components::RewardScore;
 
+mod animation;
 mod board;
 mod bubble;
+mod combo;
 mod components;
+mod config;
+mod console;
+mod debug;
+mod editor;
+pub mod events;
+mod ghost;
+mod hud;
+mod input;
+mod juice;
+mod launch;
+pub mod level;
+mod panic;
+mod particles;
+mod platform;
 mod player;
+mod powerup;
+pub mod replay;
+mod survival;
+mod theme;
+mod wave;
 
 pub struct GamePlugin;
 
 // ASSETS
-const PLAYER_FILE: &str = "player.png";
+const PLAYER_ANIMATIONS_FILE: &str = "player.anim.ron";
 const PLAYER_SIZE: (f32, f32) = (144., 75.);
 const PLAYER_SCALE: f32 = 0.5;
 
@@ -26,26 +76,73 @@ const HOOK_FILE: &str = "hook.png";
 const HOOK_SIZE: (f32, f32) = (8., 199.);
 const HOOK_WIDTH_SCALE: f32 = 1.1;
 
+const HEART_FILE: &str = "heart.png";
+
+// Gameplay tuning, reloaded whenever the file changes.
+const CONFIG_FILE: &str = "game.config.ron";
+
+const BUBBLE_SHADER_FILE: &str = "shaders/bubble.wgsl";
+const DEFAULT_THEME_FILE: &str = "themes/default.theme.ron";
+const COLORBLIND_THEME_FILE: &str = "themes/colorblind.theme.ron";
+const HIGH_CONTRAST_THEME_FILE: &str = "themes/high_contrast.theme.ron";
+
+const LEVEL_FILES: [&str; 3] = [
+    "levels/01.level.ron",
+    "levels/02.level.ron",
+    "levels/03.level.ron",
+];
+const ENDLESS_LEVEL_FILE: &str = "levels/endless.level.ron";
+// Level the editor starts on, it can open the campaign ones as well.
+const CUSTOM_LEVEL_FILE: &str = "levels/custom.level.ron";
+// Where the editor saves levels, the asset server reads them from there.
+const ASSETS_DIR: &str = "assets";
+
+const REPLAY_DIR: &str = "replays";
+const REPLAY_EXTENSION: &str = "replay.ron";
+// Kept in `REPLAY_DIR`, names the best replay of every level.
+const REPLAY_INDEX_FILE: &str = "best.ron";
+// Recent replays kept besides the ones in `REPLAY_INDEX_FILE`, older ones are deleted.
+const REPLAY_KEEP: usize = 20;
+
 // COLOR
 const WALL_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
 const BALL_COLOR: Color = Color::rgb(0.01, 0.9, 0.1);
+const FAST_BALL_COLOR: Color = Color::rgb(0.95, 0.45, 0.1);
+const BOUNCY_BALL_COLOR: Color = Color::rgb(0.2, 0.5, 0.95);
+const BALL_SHINE_COLOR: Color = Color::rgba(1., 1., 1., 0.6);
+const BALL_OUTLINE_COLOR: Color = Color::rgba(0., 0., 0., 0.6);
 const REWARD_COLOR: Color = Color::GOLD;
+const BREAKABLE_COLOR: Color = Color::rgb(0.7, 0.4, 0.2);
+const GLASS_COLOR: Color = Color::rgba(0.6, 0.85, 1.0, 0.4);
+const LADDER_COLOR: Color = Color::rgb(0.55, 0.35, 0.15);
+const CONVEYOR_COLOR: Color = Color::rgb(0.35, 0.35, 0.4);
 const SCORE_TEXT_COLOR: Color = Color::GOLD;
-const LIVES_TEXT_COLOR: Color = Color::GREEN;
+const COMBO_TEXT_COLOR: Color = Color::ORANGE;
+const LEVEL_TEXT_COLOR: Color = Color::WHITE;
+const TIMER_TEXT_COLOR: Color = Color::WHITE;
+const WEAPON_TEXT_COLOR: Color = Color::GREEN;
+const PANIC_METER_COLOR: Color = Color::ORANGE_RED;
+const PANIC_METER_BACKGROUND_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const CLOCK_COLOR: Color = Color::CYAN;
+const HOURGLASS_COLOR: Color = Color::PURPLE;
+const FLOATING_TEXT_COLOR: Color = Color::WHITE;
+const REPLAY_TEXT_COLOR: Color = Color::WHITE;
+const SPARK_COLOR: Color = Color::rgb(1., 0.85, 0.4);
+const DEATH_PARTICLE_COLOR: Color = Color::rgb(0.9, 0.2, 0.1);
 
 // GAME_CONFIGURATION
-const LIVE_COUNT: usize = 3;
-
 const TIME_STEP: f32 = 1. / 60.;
-const PLAYER_SPEED: f32 = 300.;
-const HOOK_SPEED: f32 = 100.;
-const BALL_SPEED_X: f32 = 200.;
-const BALL_SLOWDOWN: f32 = 600.;
-const REWARD_SPEED: f32 = 300.;
-const REWARD_MAX: usize = 1200;
+// More ticks than this in a frame and the game slows down instead of catching up in a rush.
+const MAX_TICKS_PER_FRAME: usize = 2;
+const FAST_FORWARD_TICKS: usize = 600;
 
 const BALL_RADIUS: f32 = 10.;
+const BUBBLE_OUTLINE_WIDTH: f32 = 2.;
+// Every size step above the smallest darkens a bubble by this much.
+const BUBBLE_SIZE_SHADING: f32 = 0.12;
+const BUBBLE_SIZE_SHADING_MIN: f32 = 0.5;
 const REWARD_SIZE: f32 = 15.;
+const POWER_UP_SIZE: f32 = 20.;
 
 const WALL_SIZE: f32 = 20.;
 const LEFT: f32 = -550.;
@@ -53,34 +150,328 @@ const RIGHT: f32 = 550.;
 const BOTTOM: f32 = -400.;
 const TOP: f32 = 400.;
 
-const LIVES_TEXT_DEFAULT: &str = "Lives: 3";
-const LIVES_TEXT_X: f32 = -225.;
-const LIVES_TEXT_Y: f32 = 450.;
-const LIVES_TEXT_SIZE: f32 = 50.0;
+// Ladders are drawn behind the player, glass in front of bubbles.
+const BACKGROUND_Z: f32 = -0.09;
+const LADDER_Z: f32 = -0.05;
+// Behind the player and everything it meets, above ladders.
+const GHOST_Z: f32 = -0.02;
+const GLASS_Z: f32 = 0.5;
+
+const HUD_TEXT_SIZE: f32 = 40.0;
+const HUD_PADDING: f32 = 10.;
+const HUD_HEART_SIZE: f32 = 32.;
+const HUD_POWER_UP_SIZE: f32 = 24.;
+
+const FLOATING_TEXT_SIZE: f32 = 30.0;
+const FLOATING_TEXT_SPEED: f32 = 60.;
+const FLOATING_TEXT_TIME: f32 = 1.;
+
+// New particles are dropped once this many are alive.
+const PARTICLE_MAX: usize = 400;
+const PARTICLE_SIZE: f32 = 6.;
+const PARTICLE_GRAVITY: f32 = 400.;
+// Above bubbles and rewards, below glass.
+const PARTICLE_Z: f32 = 0.4;
+// A pop bursts into this many particles per bubble size.
+const POP_PARTICLES: usize = 6;
+const POP_PARTICLE_SPEED: f32 = 250.;
+const POP_PARTICLE_TIME: f32 = 0.5;
+const SPARKLE_PARTICLES: usize = 8;
+const SPARKLE_PARTICLE_SPEED: f32 = 120.;
+const SPARKLE_PARTICLE_TIME: f32 = 0.4;
+const SPARK_PARTICLES: usize = 5;
+const SPARK_PARTICLE_SPEED: f32 = 200.;
+const SPARK_PARTICLE_TIME: f32 = 0.25;
+const DEATH_PARTICLES: usize = 40;
+const DEATH_PARTICLE_SPEED: f32 = 350.;
+const DEATH_PARTICLE_TIME: f32 = 1.;
+
+// Trauma is the strength of the screen shake, the shake itself grows with its square.
+const TRAUMA_DECAY: f32 = 1.5;
+const SHAKE_MAX_OFFSET: f32 = 24.;
+const SHAKE_MAX_ANGLE: f32 = 0.04;
+// Pops of bubbles at least this big shake the screen, bigger ones harder.
+const SHAKE_POP_SIZE: f32 = 3.;
+const POP_TRAUMA: f32 = 0.12;
+const DEATH_TRAUMA: f32 = 0.7;
+// Frames the game holds still for when a bubble at least this big splits.
+const HIT_STOP_SIZE: f32 = 3.;
+const HIT_STOP_FRAMES: usize = 4;
+// Scale of the view while only the last bubble is left, and how fast it gets there.
+const LAST_BUBBLE_ZOOM: f32 = 0.92;
+const ZOOM_SPEED: f32 = 2.;
+
+// Speeds the replay viewer can play at, it starts at the normal one.
+const REPLAY_SPEEDS: [f32; 5] = [0.25, 0.5, 1., 2., 4.];
+const REPLAY_NORMAL_SPEED: usize = 2;
+// How far the viewer jumps back or forward at a time.
+const REPLAY_SEEK_TICKS: usize = 300;
+const REPLAY_TEXT_SIZE: f32 = 24.;
+
+const GHOST_ALPHA: f32 = 0.35;
+const GHOST_TEXT_SIZE: f32 = 32.;
+const GHOST_AHEAD_COLOR: Color = Color::rgb(0.3, 0.9, 0.3);
+const GHOST_BEHIND_COLOR: Color = Color::rgb(0.9, 0.3, 0.3);
+
+// Everything placed in the editor snaps to a grid with cells this big.
+const EDITOR_GRID_SIZE: f32 = 20.;
+const EDITOR_GRID_COLOR: Color = Color::rgba(1., 1., 1., 0.08);
+const EDITOR_CURSOR_COLOR: Color = Color::rgba(1., 1., 1., 0.35);
+const EDITOR_TEXT_SIZE: f32 = 24.;
+const EDITOR_BUBBLE_SIZE_MIN: f32 = 1.;
+const EDITOR_BUBBLE_SIZE_MAX: f32 = 5.;
+const EDITOR_CONVEYOR_SPEED: f32 = 120.;
 
-const SCORE_TEXT_DEFAULT: &str = "Score: 0";
-const SCORE_TEXT_X: f32 = 225.;
-const SCORE_TEXT_Y: f32 = 450.;
-const SCORE_TEXT_SIZE: f32 = 40.0;
+const DEBUG_TEXT_SIZE: f32 = 20.;
+const DEBUG_TEXT_TOP: f32 = 70.;
+const DEBUG_TEXT_COLOR: Color = Color::YELLOW;
+const DEBUG_LINE_WIDTH: f32 = 2.;
+// Above everything in the arena, below the letterbox.
+const DEBUG_Z: f32 = 50.;
+// Length of a velocity vector per unit of speed.
+const DEBUG_VELOCITY_SCALE: f32 = 0.25;
+const DEBUG_PLAYER_COLOR: Color = Color::GREEN;
+const DEBUG_HOOK_COLOR: Color = Color::YELLOW;
+const DEBUG_BUBBLE_COLOR: Color = Color::RED;
+const DEBUG_REWARD_COLOR: Color = Color::GOLD;
+const DEBUG_POWER_UP_COLOR: Color = Color::FUCHSIA;
+const DEBUG_WALL_COLOR: Color = Color::CYAN;
+const DEBUG_VELOCITY_COLOR: Color = Color::ORANGE;
+const DEBUG_INSPECTED_COLOR: Color = Color::WHITE;
+// Keys setting the speed of the simulation, pressing the one in use again goes back to normal.
+const DEBUG_SPEED_KEYS: [(KeyCode, f32); 3] =
+    [(KeyCode::F7, 0.25), (KeyCode::F8, 0.5), (KeyCode::F9, 2.)];
+
+const CONSOLE_TEXT_SIZE: f32 = 20.;
+const CONSOLE_TEXT_COLOR: Color = Color::WHITE;
+const CONSOLE_BACKGROUND_COLOR: Color = Color::rgba(0., 0., 0., 0.8);
+// Lines of output kept above the prompt.
+const CONSOLE_LINES: usize = 12;
+
+const PANIC_METER_X: f32 = 20.;
+const PANIC_METER_Y: f32 = 70.;
+const PANIC_METER_SIZE: (f32, f32) = (200., 16.);
 
 // RESOURCES
 #[derive(Resource)]
 struct GameTextures {
-    player: Handle<Image>,
+    player: Handle<PlayerAnimations>,
     hook: Handle<Image>,
+    heart: Handle<Image>,
 }
 
-#[derive(Default)]
-struct CollisionEvent;
+#[derive(Resource, Deref, DerefMut)]
+struct GameRng(StdRng);
 
 #[derive(Resource, Default)]
 struct Score {
     score: usize,
 }
 
+// Set from the console in debug builds.
+#[derive(Resource, Default)]
+struct Cheats {
+    // Bubbles pass through the player.
+    god_mode: bool,
+    // Bubbles hold still.
+    frozen: bool,
+    // A cheat command ran this round.
+    used: bool,
+}
+
+impl Cheats {
+    // A cheated round keeps no records, scores or replay.
+    fn cheated(&self) -> bool {
+        self.used || self.god_mode || self.frozen
+    }
+}
+
+// Stage running the game in fixed ticks of `TIME_STEP`, any number of them in a frame. Its
+// systems run one at a time in the same order every time, so a replayed run plays out exactly
+// like the recorded one.
+#[derive(StageLabel)]
+pub struct SimulationStage;
+
+// The phases of a tick, each one running after the one before it. Systems of a phase that touch
+// the same data or spawn the same kind of entity are ordered against each other as well, Bevy
+// would pick a different order for them every time the game starts.
+#[derive(SystemLabel, Clone, Copy)]
+pub enum TickLabel {
+    // Deciding this tick's `Actions`.
+    Input,
+    // Everything moves, appears and counts down.
+    Movement,
+    Collisions,
+    // Acting on the collisions: pops, points, lives and combos.
+    Reactions,
+    LevelCleared,
+    // Ending the round and keeping its results.
+    GameEnd,
+}
+
+impl TickLabel {
+    // Systems of this phase, after the ones of the phase before it.
+    pub fn set(self) -> SystemSet {
+        let set = SystemSet::new().label(self);
+        match self {
+            TickLabel::Input => set,
+            TickLabel::Movement => set.after(TickLabel::Input),
+            TickLabel::Collisions => set.after(TickLabel::Movement),
+            TickLabel::Reactions => set.after(TickLabel::Collisions),
+            TickLabel::LevelCleared => set.after(TickLabel::Reactions),
+            TickLabel::GameEnd => set.after(TickLabel::LevelCleared),
+        }
+    }
+}
+
+// Decides how many ticks of the game run this frame.
 #[derive(Resource)]
+pub struct Simulation {
+    // Frames left to hold the game still for.
+    hit_stop: usize,
+    // Game time not yet run as ticks.
+    accumulator: f32,
+    ticks: usize,
+    // Multiplies the speed set in the accessibility settings.
+    pub speed: f32,
+    pub paused: bool,
+    // Runs a single tick while paused.
+    pub step: bool,
+    // Ticks to run as fast as possible, ignoring the clock.
+    pub fast_forward: usize,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self {
+            hit_stop: 0,
+            accumulator: 0.,
+            ticks: 0,
+            speed: 1.,
+            paused: false,
+            step: false,
+            fast_forward: 0,
+        }
+    }
+}
+
+impl Simulation {
+    pub fn hit_stop(&mut self, frames: usize) {
+        self.hit_stop = self.hit_stop.max(frames);
+    }
+
+    // Skips the ticks left in this frame, used once the game is over.
+    fn stop(&mut self) {
+        self.ticks = 0;
+        self.fast_forward = 0;
+    }
+}
+
+fn simulation_frame_system(
+    mut simulation: ResMut<Simulation>,
+    accessibility: Res<Accessibility>,
+    time: Res<Time>,
+) {
+    if simulation.fast_forward > 0 {
+        simulation.ticks = simulation.fast_forward.min(FAST_FORWARD_TICKS);
+        simulation.fast_forward -= simulation.ticks;
+        simulation.accumulator = 0.;
+        return;
+    }
+
+    if simulation.paused {
+        simulation.ticks = simulation.step as usize;
+        simulation.step = false;
+        simulation.accumulator = 0.;
+        return;
+    }
+
+    if simulation.hit_stop > 0 {
+        simulation.hit_stop -= 1;
+        simulation.ticks = 0;
+        return;
+    }
+
+    let speed = accessibility.speed() * simulation.speed;
+    simulation.accumulator += time.delta_seconds() * speed;
+    let ticks = (simulation.accumulator / TIME_STEP) as usize;
+    // A long frame doesn't make the game catch up in a rush.
+    let max_ticks = (MAX_TICKS_PER_FRAME as f32 * speed.max(1.)).ceil() as usize;
+    simulation.ticks = ticks.min(max_ticks);
+    simulation.accumulator = if ticks > max_ticks {
+        0.
+    } else {
+        simulation.accumulator - ticks as f32 * TIME_STEP
+    };
+}
+
+// The first tick waits for the player's sprite sheet and the level's bubbles, so however long
+// they take to load a round, or its replay, starts out the same.
+fn simulation_tick_criteria(
+    state: Res<State<AppState>>,
+    mut simulation: ResMut<Simulation>,
+    clock: Option<Res<GameClock>>,
+    bubble_state: Res<BubbleState>,
+    player_atlas: Option<Res<PlayerAtlas>>,
+) -> ShouldRun {
+    let started = clock.is_some_and(|clock| clock.ticks > 0);
+    let ready = started || (bubble_state.spawned && player_atlas.is_some());
+    if *state.current() == AppState::Game && ready && simulation.ticks > 0 {
+        simulation.ticks -= 1;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        ShouldRun::No
+    }
+}
+
+// Fixed steps since the round started.
+#[derive(Resource, Default)]
+struct GameClock {
+    ticks: usize,
+}
+
+impl GameClock {
+    fn seconds(&self) -> usize {
+        (self.ticks as f32 * TIME_STEP) as usize
+    }
+
+    // Counted from the ticks rather than measured, so a run times the same on any machine.
+    fn millis(&self) -> u64 {
+        (self.ticks as f64 * TIME_STEP as f64 * 1000.).round() as u64
+    }
+}
+
+// Level shown on the HUD, the endless modes count their waves with it.
+#[derive(Resource)]
+struct CurrentLevel {
+    number: usize,
+}
+
+impl Default for CurrentLevel {
+    fn default() -> Self {
+        Self { number: 1 }
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default)]
+enum Weapon {
+    #[default]
+    Harpoon,
+}
+
+impl Weapon {
+    fn name(&self) -> &'static str {
+        match self {
+            Weapon::Harpoon => "Harpoon",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
 struct PlayerState {
     lives: usize,
+    // Lives lost this round.
+    hits: usize,
     is_alive: bool,
     hook_shoted: bool,
 }
@@ -88,6 +479,7 @@ struct PlayerState {
 impl PlayerState {
     fn kill(&mut self) {
         self.lives -= 1;
+        self.hits += 1;
         self.is_alive = false;
     }
     fn spawn(&mut self) {
@@ -107,43 +499,25 @@ impl PlayerState {
     }
 
     fn restart(&mut self) {
-        self.lives = LIVE_COUNT;
+        self.lives = 0;
+        self.hits = 0;
         self.is_alive = false;
         self.hook_shoted = false;
     }
 }
 
-impl Default for PlayerState {
-    fn default() -> Self {
-        Self {
-            lives: LIVE_COUNT,
-            is_alive: false,
-            hook_shoted: false,
-        }
-    }
-}
-
-#[derive(Resource)]
+#[derive(Resource, Default)]
 struct BubbleState {
     count: usize,
-    sizes: Vec<f32>,
-    positions: Vec<(f32, f32)>,
+    popped: usize,
     spawned: bool,
-}
-
-impl Default for BubbleState {
-    fn default() -> Self {
-        Self {
-            count: 1,
-            spawned: false,
-            sizes: vec![4.],
-            positions: vec![(0., 0.)],
-        }
-    }
+    // The board's obstacles, spawned along with the level's bubbles.
+    obstacles_spawned: bool,
 }
 
 impl BubbleState {
-    fn spawn(&mut self) {
+    fn spawn(&mut self, count: usize) {
+        self.count = count;
         self.spawned = true;
     }
 
@@ -151,27 +525,46 @@ impl BubbleState {
         self.spawned = false;
     }
 
+    fn pop(&mut self) {
+        self.count -= 1;
+        self.popped += 1;
+    }
+
     fn restart(&mut self) {
-        self.count = 1;
+        self.count = 0;
+        self.popped = 0;
         self.spawned = false;
-        self.sizes = vec![4.];
-        self.positions = vec![(0., 0.)];
+        self.obstacles_spawned = false;
     }
 }
 
 fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn(Camera2dBundle::default());
-
     let game_textures = GameTextures {
-        player: asset_server.load(PLAYER_FILE),
+        player: asset_server.load(PLAYER_ANIMATIONS_FILE),
         hook: asset_server.load(HOOK_FILE),
+        heart: asset_server.load(HEART_FILE),
     };
 
     commands.insert_resource(game_textures);
+    commands.insert_resource(LevelHandles {
+        levels: LEVEL_FILES
+            .iter()
+            .map(|file| asset_server.load(*file))
+            .collect(),
+        selected: 0,
+        test: None,
+        endless: asset_server.load(ENDLESS_LEVEL_FILE),
+    });
 }
 
-fn velocity_system(mut query: Query<(&mut Transform, &mut Movement), Without<Hook>>) {
-    for (mut transform, mut movement) in &mut query {
+fn velocity_system(
+    cheats: Res<Cheats>,
+    mut query: Query<(&mut Transform, &mut Movement, Option<&Bubble>), Without<Hook>>,
+) {
+    for (mut transform, mut movement, bubble) in &mut query {
+        if cheats.frozen && bubble.is_some() {
+            continue;
+        }
         transform.translation.x += movement.v_x * TIME_STEP;
         transform.translation.y += movement.v_y * TIME_STEP;
         movement.v_y -= movement.a * TIME_STEP;
@@ -186,12 +579,15 @@ fn rope_hook_system(mut query: Query<(&mut Transform, &Movement), With<Hook>>) {
     }
 }
 
+// This is intended.
+#[allow(clippy::type_complexity)]
 fn hook_wall_collision_system(
     mut commands: Commands,
     mut player_state: ResMut<PlayerState>,
     hook_query: Query<(Entity, &Transform), With<Hook>>,
-    wall_query: Query<&Transform, With<Wall>>,
+    wall_query: Query<(Entity, &Transform, Option<&Breakable>, Option<&Ceiling>), With<Wall>>,
     mut collision_events: EventWriter<CollisionEvent>,
+    mut hook_missed_events: EventWriter<HookMissedEvent>,
 ) {
     let mut despawned_entities = HashSet::new();
 
@@ -199,7 +595,7 @@ fn hook_wall_collision_system(
         if despawned_entities.contains(&hook_entity) {
             continue;
         }
-        for wall_transform in wall_query.iter() {
+        for (wall_entity, wall_transform, breakable, ceiling) in wall_query.iter() {
             if despawned_entities.contains(&hook_entity) {
                 continue;
             }
@@ -215,9 +611,26 @@ fn hook_wall_collision_system(
             );
 
             if collision.is_some() {
-                collision_events.send_default();
+                collision_events.send(CollisionEvent {
+                    first: Collider::Hook,
+                    second: if breakable.is_some() {
+                        Collider::Breakable
+                    } else if ceiling.is_some() {
+                        Collider::Ceiling
+                    } else {
+                        Collider::Wall
+                    },
+                });
                 commands.entity(hook_entity).despawn();
                 despawned_entities.insert(hook_entity);
+                if breakable.is_some() {
+                    commands.entity(wall_entity).despawn();
+                } else {
+                    hook_missed_events.send(HookMissedEvent {
+                        position: hook_transform.translation.truncate(),
+                        ceiling: ceiling.is_some(),
+                    });
+                }
                 player_state.unhook();
             }
         }
@@ -225,11 +638,13 @@ fn hook_wall_collision_system(
 }
 
 fn bubble_wall_collision_system(
-    mut bubble_query: Query<(&mut Movement, &Transform, &BubbleSize), With<Bubble>>,
-    wall_query: Query<&Transform, With<Wall>>,
+    config: Res<GameConfig>,
+    mut bubble_query: Query<(&mut Movement, &Transform, &BubbleSize, &BubbleKind), With<Bubble>>,
+    wall_query: Query<&Transform, (With<Wall>, Without<Glass>)>,
     mut collision_events: EventWriter<CollisionEvent>,
 ) {
-    for (mut bubble_movement, bubble_transform, bubble_size) in bubble_query.iter_mut() {
+    for (mut bubble_movement, bubble_transform, bubble_size, bubble_kind) in bubble_query.iter_mut()
+    {
         for wall_transform in wall_query.iter() {
             let collision = collide(
                 bubble_transform.translation,
@@ -239,49 +654,57 @@ fn bubble_wall_collision_system(
             );
 
             if let Some(collision) = collision {
-                collision_events.send_default();
-
-                match collision {
-                    Collision::Left => {
+                // Only reflect when moving into the wall, so a bubble that is still
+                // overlapping a platform after a bounce doesn't get stuck in it.
+                let bounced = match collision {
+                    Collision::Left if bubble_movement.v_x > 0. => {
                         bubble_movement.v_x = -bubble_movement.v_x;
+                        true
                     }
-                    Collision::Right => {
+                    Collision::Right if bubble_movement.v_x < 0. => {
                         bubble_movement.v_x = -bubble_movement.v_x;
+                        true
                     }
-                    Collision::Top => {
-                        bubble_movement.v_y = BALL_SPEED_X * bubble_size.size;
+                    Collision::Top if bubble_movement.v_y < 0. => {
+                        bubble_movement.v_y = config.bubble.bounce(*bubble_kind, bubble_size.size);
+                        true
                     }
-                    Collision::Bottom => {
+                    Collision::Bottom if bubble_movement.v_y > 0. => {
                         bubble_movement.v_y = -bubble_movement.v_y;
+                        true
                     }
-                    Collision::Inside => {}
+                    _ => false,
+                };
+                // A bubble overlaps a wall for a few ticks, it bounces off it once.
+                if bounced {
+                    collision_events.send(CollisionEvent {
+                        first: Collider::Bubble {
+                            size: bubble_size.size,
+                        },
+                        second: Collider::Wall,
+                    });
                 }
             }
         }
     }
 }
 
-// This is intended.
-#[allow(clippy::too_many_arguments)]
 fn bubble_hook_collision_system(
     mut commands: Commands,
     mut player_state: ResMut<PlayerState>,
     hook_query: Query<(Entity, &Transform), With<Hook>>,
-    bubble_query: Query<(Entity, &Transform, &BubbleSize), With<Bubble>>,
+    bubble_query: Query<(Entity, &Transform, &BubbleSize, &BubbleKind, &Movement), With<Bubble>>,
     mut collision_events: EventWriter<CollisionEvent>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut bubble_state: ResMut<BubbleState>,
-    mut game_state: ResMut<State<AppState>>,
-    mut scores: ResMut<Scores>,
-    current_score: Res<Score>,
+    mut bubble_popped_events: EventWriter<BubblePoppedEvent>,
 ) {
     let mut despawned_entities = HashSet::new();
     for (hook_entity, hook_transform) in hook_query.iter() {
         if despawned_entities.contains(&hook_entity) {
             continue;
         }
-        for (bubble_entity, bubble_transform, bubble_size) in bubble_query.iter() {
+        for (bubble_entity, bubble_transform, bubble_size, bubble_kind, bubble_movement) in
+            bubble_query.iter()
+        {
             if despawned_entities.contains(&hook_entity)
                 || despawned_entities.contains(&bubble_entity)
             {
@@ -299,104 +722,40 @@ fn bubble_hook_collision_system(
             );
 
             if collision.is_some() {
-                collision_events.send_default();
+                collision_events.send(CollisionEvent {
+                    first: Collider::Bubble {
+                        size: bubble_size.size,
+                    },
+                    second: Collider::Hook,
+                });
                 commands.entity(hook_entity).despawn();
                 despawned_entities.insert(hook_entity);
                 commands.entity(bubble_entity).despawn();
-                let (bubble_position_x, bubble_position_y) = (
-                    bubble_transform.translation.x,
-                    bubble_transform.translation.y,
-                );
-                bubble_state.count -= 1;
-                if bubble_size.size > 2. {
-                    bubble_state.count += 2;
-                    let new_bubble_size = bubble_size.size - 1.;
-                    for a in 0..2 {
-                        let direction = if a == 0 { -1. } else { 1. };
-                        commands.spawn((
-                            MaterialMesh2dBundle {
-                                mesh: meshes.add(shape::Circle::default().into()).into(),
-                                material: materials.add(ColorMaterial::from(BALL_COLOR)),
-                                transform: Transform {
-                                    translation: Vec3::new(
-                                        bubble_position_x,
-                                        bubble_position_y,
-                                        0.,
-                                    ),
-                                    scale: Vec3::new(
-                                        BALL_RADIUS * new_bubble_size,
-                                        BALL_RADIUS * new_bubble_size,
-                                        0.,
-                                    ),
-                                    ..default()
-                                },
-                                ..default()
-                            },
-                            Bubble,
-                            Movement {
-                                v_x: direction * BALL_SPEED_X,
-                                v_y: BALL_SPEED_X * new_bubble_size,
-                                a: BALL_SLOWDOWN,
-                            },
-                            BubbleSize {
-                                size: new_bubble_size,
-                            },
-                            GameScreen,
-                        ));
-                    }
-                }
-                commands.spawn((
-                    MaterialMesh2dBundle {
-                        mesh: meshes.add(shape::Quad::default().into()).into(),
-                        material: materials.add(ColorMaterial::from(REWARD_COLOR)),
-                        transform: Transform {
-                            translation: Vec3::new(bubble_position_x, bubble_position_y, 0.),
-                            scale: Vec3::new(REWARD_SIZE, REWARD_SIZE, 0.),
-                            ..default()
-                        },
-                        ..default()
-                    },
-                    Movement {
-                        v_x: 0.,
-                        v_y: -REWARD_SPEED,
-                        a: 0.,
-                    },
-                    Reward,
-                    RewardScore {
-                        score: REWARD_MAX / (bubble_size.size as usize),
-                    },
-                    GameScreen,
-                ));
+                despawned_entities.insert(bubble_entity);
+                bubble_popped_events.send(BubblePoppedEvent {
+                    size: bubble_size.size,
+                    kind: *bubble_kind,
+                    position: bubble_transform.translation.truncate(),
+                    speed: bubble_movement.v_x.abs(),
+                });
                 player_state.unhook();
             }
         }
     }
-    if bubble_state.count == 0 {
-        game_state.set(AppState::Menu).unwrap();
-        bubble_state.despawn();
-        scores.score_list.push(current_score.score);
-        player_state.restart();
-        bubble_state.restart();
-    }
 }
 
-// This is intended.
-#[allow(clippy::too_many_arguments)]
 fn bubble_player_collision_system(
     mut commands: Commands,
-    mut player_state: ResMut<PlayerState>,
-    current_score: ResMut<Score>,
-    bubble_query: Query<&Transform, With<Bubble>>,
+    player_state: Res<PlayerState>,
+    cheats: Res<Cheats>,
+    bubble_query: Query<(&Transform, &BubbleSize), With<Bubble>>,
     player_query: Query<(Entity, &Transform), With<Player>>,
     mut collision_events: EventWriter<CollisionEvent>,
-    mut game_state: ResMut<State<AppState>>,
-    mut bubble_state: ResMut<BubbleState>,
-    mut scores: ResMut<Scores>,
-    mut lives_text_query: Query<&mut Text, With<LivesText>>,
+    mut player_hit_events: EventWriter<PlayerHitEvent>,
 ) {
-    if player_state.is_alive {
+    if player_state.is_alive && !cheats.god_mode {
         if let Ok((player_entity, player_transform)) = player_query.get_single() {
-            for bubble_transform in bubble_query.iter() {
+            for (bubble_transform, bubble_size) in bubble_query.iter() {
                 let collision = collide(
                     player_transform.translation,
                     Vec2::new(
@@ -408,21 +767,16 @@ fn bubble_player_collision_system(
                 );
 
                 if collision.is_some() {
-                    collision_events.send_default();
+                    collision_events.send(CollisionEvent {
+                        first: Collider::Player,
+                        second: Collider::Bubble {
+                            size: bubble_size.size,
+                        },
+                    });
                     commands.entity(player_entity).despawn();
-                    player_state.kill();
-
-                    if let Ok(mut lives_text) = lives_text_query.get_single_mut() {
-                        lives_text.sections[0].value = format!("Lives: {}", player_state.lives);
-                    }
-
-                    if player_state.is_completely_dead() {
-                        game_state.set(AppState::Menu).unwrap();
-                        bubble_state.despawn();
-                        scores.score_list.push(current_score.score);
-                        player_state.restart();
-                        bubble_state.restart();
-                    }
+                    player_hit_events.send(PlayerHitEvent {
+                        position: player_transform.translation.truncate(),
+                    });
                     break;
                 }
             }
@@ -432,12 +786,11 @@ fn bubble_player_collision_system(
 
 fn reward_player_collision_system(
     mut commands: Commands,
-    player_state: ResMut<PlayerState>,
+    player_state: Res<PlayerState>,
     reward_query: Query<(Entity, &Transform, &RewardScore), With<Reward>>,
     player_query: Query<&Transform, With<Player>>,
     mut collision_events: EventWriter<CollisionEvent>,
-    mut current_score: ResMut<Score>,
-    mut score_text_query: Query<&mut Text, With<ScoreText>>,
+    mut reward_collected_events: EventWriter<RewardCollectedEvent>,
 ) {
     if player_state.is_alive {
         if let Ok(player_transform) = player_query.get_single() {
@@ -453,12 +806,15 @@ fn reward_player_collision_system(
                 );
 
                 if collision.is_some() {
-                    collision_events.send_default();
+                    collision_events.send(CollisionEvent {
+                        first: Collider::Player,
+                        second: Collider::Reward,
+                    });
                     commands.entity(reward_entity).despawn();
-                    current_score.score += reward_score.score;
-                    if let Ok(mut score_text) = score_text_query.get_single_mut() {
-                        score_text.sections[0].value = format!("Score: {}", current_score.score);
-                    }
+                    reward_collected_events.send(RewardCollectedEvent {
+                        score: reward_score.score,
+                        position: reward_transform.translation.truncate(),
+                    });
                     break;
                 }
             }
@@ -482,7 +838,10 @@ fn reward_wall_collision_system(
             );
 
             if collision.is_some() {
-                collision_events.send_default();
+                collision_events.send(CollisionEvent {
+                    first: Collider::Reward,
+                    second: Collider::Wall,
+                });
                 commands.entity(reward_entity).despawn();
                 break;
             }
@@ -490,31 +849,270 @@ fn reward_wall_collision_system(
     }
 }
 
-fn score_system(mut commands: Commands) {
+fn scoring_system(
+    mut commands: Commands,
+    mut current_score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    fonts: Res<Fonts>,
+    config: Res<GameConfig>,
+    mut bubble_popped_events: EventReader<BubblePoppedEvent>,
+    mut reward_collected_events: EventReader<RewardCollectedEvent>,
+) {
+    for bubble_popped in bubble_popped_events.iter() {
+        let pop_score = config.bubble.pop_score / (bubble_popped.size as usize).max(1);
+        let multiplier = combo.pop(&config.combo);
+        current_score.score += pop_score * multiplier;
+        spawn_floating_text(
+            &mut commands,
+            &fonts,
+            bubble_popped.position,
+            format!("+{} x{}", pop_score, multiplier),
+        );
+    }
+
+    for reward_collected in reward_collected_events.iter() {
+        current_score.score += reward_collected.score;
+    }
+}
+
+fn lives_system(
+    mut player_state: ResMut<PlayerState>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    mut game_over_events: EventWriter<GameOverEvent>,
+) {
+    for _ in player_hit_events.iter() {
+        player_state.kill();
+        if player_state.is_completely_dead() {
+            game_over_events.send(GameOverEvent);
+        }
+    }
+}
+
+fn level_cleared_system(
+    mut bubble_state: ResMut<BubbleState>,
+    game_mode: Res<GameMode>,
+    mut level_cleared_events: EventWriter<LevelClearedEvent>,
+) {
+    if game_mode.ends_when_cleared() && bubble_state.spawned && bubble_state.count == 0 {
+        level_cleared_events.send(LevelClearedEvent);
+        bubble_state.despawn();
+    }
+}
+
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn game_end_system(
+    mut game_state: ResMut<State<AppState>>,
+    mut simulation: ResMut<Simulation>,
+    mut scores: ResMut<Scores>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+    current_score: Res<Score>,
+    cheats: Res<Cheats>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut level_cleared_events: EventReader<LevelClearedEvent>,
+    mut game_over_events: EventReader<GameOverEvent>,
+) {
+    // Both can arrive in the same tick, but the game only ends once.
+    let level_cleared = level_cleared_events.iter().count() > 0;
+    let game_over = game_over_events.iter().count() > 0;
+    if level_cleared || game_over {
+        // A test-played level goes back to the editor, unscored.
+        if level_handles.testing() {
+            game_state.set(AppState::Editor).unwrap();
+            simulation.stop();
+            return;
+        }
+        game_state.set(AppState::Menu).unwrap();
+        simulation.stop();
+        // A watched replay doesn't score again.
+        if replay_playback.is_none() && !cheats.cheated() && *game_mode != GameMode::TimeAttack {
+            scores.push(*game_mode, current_score.score);
+        }
+    }
+}
+
+// Keeps the results of a cleared level, the campaign unlocks the next one with it.
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn records_system(
+    mut records: ResMut<Records>,
+    asset_server: Res<AssetServer>,
+    levels: Res<Assets<Level>>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+    current_score: Res<Score>,
+    clock: Res<GameClock>,
+    player_state: Res<PlayerState>,
+    cheats: Res<Cheats>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut level_cleared_events: EventReader<LevelClearedEvent>,
+) {
+    if level_cleared_events.iter().count() == 0
+        || replay_playback.is_some()
+        || level_handles.testing()
+        || cheats.cheated()
+    {
+        return;
+    }
+    let level = level_handles.path(&asset_server, *game_mode);
+    match *game_mode {
+        GameMode::TimeAttack => records.finish(level, clock.millis()),
+        GameMode::Campaign => {
+            // A star for clearing it, one for not losing a life and one for beating its par.
+            let par = levels
+                .get(level_handles.current(*game_mode))
+                .and_then(|level| level.par);
+            let stars = 1
+                + (player_state.hits == 0) as u8
+                + par.is_some_and(|par| clock.millis() as f32 / 1000. <= par) as u8;
+            records.clear(
+                level,
+                LevelRecord {
+                    score: current_score.score,
+                    time: clock.millis(),
+                    stars,
+                },
+            );
+        }
+        GameMode::Survival | GameMode::Panic => {}
+    }
+}
+
+// Whichever way the round ends, the next one starts from scratch.
+fn round_reset_system(
+    mut player_state: ResMut<PlayerState>,
+    mut bubble_state: ResMut<BubbleState>,
+) {
+    player_state.restart();
+    bubble_state.restart();
+}
+
+fn score_system(
+    mut commands: Commands,
+    mut cheats: ResMut<Cheats>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+) {
+    cheats.used = false;
     commands.insert_resource(Score::default());
+    commands.insert_resource(GameClock::default());
+    // The endless modes count their waves from the first one instead.
+    commands.insert_resource(if game_mode.ends_when_cleared() {
+        CurrentLevel {
+            number: level_handles.selected + 1,
+        }
+    } else {
+        CurrentLevel::default()
+    });
+    commands.insert_resource(Weapon::default());
+    commands.insert_resource(Simulation::default());
+}
+
+fn lives_setup_system(
+    mut player_state: ResMut<PlayerState>,
+    accessibility: Res<Accessibility>,
+    config: Res<GameConfig>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+) {
+    // A replay keeps the lives it was recorded with.
+    let assist = replay_playback
+        .filter(|playback| playback.is_watched())
+        .map_or(accessibility.assist, |playback| playback.replay.assist);
+    player_state.lives = if assist {
+        config.lives + config.assist_lives
+    } else {
+        config.lives
+    };
+}
+
+fn clock_system(mut clock: ResMut<GameClock>) {
+    clock.ticks += 1;
 }
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<CollisionEvent>()
-            .add_plugin(BoardPlugin)
-            .add_plugin(PlayerPlugin)
-            .add_plugin(BubblePlugin)
-            .add_startup_system(setup_system)
-            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(score_system))
-            .add_system_set(
-                SystemSet::on_update(AppState::Game)
-                    .with_system(rope_hook_system)
-                    .with_system(hook_wall_collision_system)
-                    .with_system(bubble_wall_collision_system)
-                    .with_system(bubble_hook_collision_system)
-                    .with_system(bubble_player_collision_system)
-                    .with_system(reward_player_collision_system)
-                    .with_system(reward_wall_collision_system)
-                    .with_system(velocity_system),
-            )
-            .add_system_set(
-                SystemSet::on_exit(AppState::Game).with_system(despawn_screen::<GameScreen>),
-            );
+        app.add_stage_after(
+            CoreStage::Update,
+            SimulationStage,
+            SystemStage::single_threaded().with_run_criteria(simulation_tick_criteria),
+        )
+        .add_plugin(EventsPlugin)
+        .add_plugin(ConfigPlugin)
+        .add_plugin(LevelPlugin)
+        .add_plugin(ThemePlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(BoardPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(BubblePlugin)
+        .add_plugin(PlatformPlugin)
+        .add_plugin(ComboPlugin)
+        .add_plugin(SurvivalPlugin)
+        .add_plugin(PowerUpPlugin)
+        .add_plugin(PanicPlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(ParticlesPlugin)
+        .add_plugin(JuicePlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(GhostPlugin)
+        .add_plugin(EditorPlugin)
+        .add_plugin(LaunchPlugin)
+        .add_plugin(DebugPlugin)
+        .add_plugin(ConsolePlugin)
+        .init_resource::<Simulation>()
+        .init_resource::<Cheats>()
+        .add_startup_system(setup_system)
+        .add_system_to_stage(CoreStage::PreUpdate, simulation_frame_system)
+        .add_system_set(
+            SystemSet::on_enter(AppState::Game)
+                .with_system(score_system)
+                .with_system(lives_setup_system.after(ConfigLabel)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Movement
+                .set()
+                .with_system(velocity_system)
+                .with_system(rope_hook_system.after(velocity_system)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Collisions
+                .set()
+                .with_system(bubble_wall_collision_system)
+                .with_system(bubble_hook_collision_system.after(bubble_wall_collision_system))
+                .with_system(hook_wall_collision_system.after(bubble_hook_collision_system))
+                .with_system(bubble_player_collision_system.after(hook_wall_collision_system))
+                .with_system(reward_player_collision_system.after(bubble_player_collision_system))
+                .with_system(reward_wall_collision_system.after(reward_player_collision_system)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Reactions
+                .set()
+                .with_system(scoring_system)
+                .with_system(lives_system),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::LevelCleared
+                .set()
+                .with_system(level_cleared_system),
+        )
+        // The round ends in the tick it was lost or cleared in, with its last points.
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::GameEnd
+                .set()
+                .with_system(game_end_system)
+                .with_system(clock_system)
+                .with_system(records_system.after(clock_system)),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::Game)
+                .with_system(despawn_screen::<GameScreen>)
+                .with_system(round_reset_system),
+        );
     }
 }
@@ -0,0 +1,297 @@
+use crate::game::components::{
+    AnimationState, Animator, GameScreen, Player, PlayerBody, PlayerCorpse,
+};
+use crate::game::events::{HookFiredEvent, PlayerHitEvent};
+use crate::game::{GameTextures, SimulationStage, TickLabel, PLAYER_SCALE, TIME_STEP};
+use crate::AppState;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<PlayerAnimations>()
+            .init_asset_loader::<PlayerAnimationsLoader>()
+            .add_system(player_atlas_system)
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(player_animation_state_system)
+                    .with_system(player_death_system),
+            )
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Movement.set().with_system(animation_system),
+            );
+    }
+}
+
+// Sprite sheet of the player and the frames every animation state plays.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "0c3b8f4e-5d0a-4a47-9d43-2f5f6f8e1c27"]
+pub struct PlayerAnimations {
+    texture: String,
+    frame_size: (f32, f32),
+    columns: usize,
+    rows: usize,
+    animations: HashMap<AnimationState, Animation>,
+}
+
+#[derive(Deserialize)]
+struct Animation {
+    frames: Vec<usize>,
+    frame_time: f32,
+    #[serde(default)]
+    looping: bool,
+    #[serde(default)]
+    flip_x: bool,
+}
+
+impl Animation {
+    fn is_finished(&self, elapsed: f32) -> bool {
+        !self.looping && elapsed >= self.frame_time * self.frames.len() as f32
+    }
+
+    fn frame(&self, elapsed: f32) -> usize {
+        let frame = (elapsed / self.frame_time) as usize;
+        if self.looping {
+            self.frames[frame % self.frames.len()]
+        } else {
+            self.frames[frame.min(self.frames.len() - 1)]
+        }
+    }
+}
+
+impl PlayerAnimations {
+    fn get(&self, state: AnimationState) -> Option<&Animation> {
+        self.animations.get(&state)
+    }
+
+    // Every animation needs a frame to show, and all of them have to be on the sheet.
+    fn validate(&self) -> Result<(), String> {
+        let frame_count = self.columns * self.rows;
+        for (state, animation) in &self.animations {
+            if animation.frames.is_empty() {
+                return Err(format!("{:?} has no frames", state));
+            }
+            if animation.frame_time <= 0. {
+                return Err(format!(
+                    "{:?} has a frame time of {}",
+                    state, animation.frame_time
+                ));
+            }
+            if let Some(frame) = animation.frames.iter().find(|&&frame| frame >= frame_count) {
+                return Err(format!(
+                    "{:?} shows frame {}, the sheet has {}",
+                    state, frame, frame_count
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Built from the animations once they're loaded, the player can't spawn before that.
+#[derive(Resource)]
+pub struct PlayerAtlas {
+    pub atlas: Handle<TextureAtlas>,
+}
+
+#[derive(Default)]
+struct PlayerAnimationsLoader;
+
+impl AssetLoader for PlayerAnimationsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let animations = ron::de::from_bytes::<PlayerAnimations>(bytes)?;
+            animations.validate().map_err(bevy::asset::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(animations));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
+
+fn player_atlas_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_textures: Option<Res<GameTextures>>,
+    player_animations: Res<Assets<PlayerAnimations>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    player_atlas: Option<Res<PlayerAtlas>>,
+) {
+    if player_atlas.is_some() {
+        return;
+    }
+
+    if let Some(animations) = game_textures
+        .as_ref()
+        .and_then(|game_textures| player_animations.get(&game_textures.player))
+    {
+        let atlas = TextureAtlas::from_grid(
+            asset_server.load(animations.texture.as_str()),
+            Vec2::new(animations.frame_size.0, animations.frame_size.1),
+            animations.columns,
+            animations.rows,
+            None,
+            None,
+        );
+        commands.insert_resource(PlayerAtlas {
+            atlas: texture_atlases.add(atlas),
+        });
+    }
+}
+
+// Picks the player's animation from what it's doing, a shot plays through before anything else.
+fn player_animation_state_system(
+    game_textures: Res<GameTextures>,
+    player_animations: Res<Assets<PlayerAnimations>>,
+    mut hook_fired_events: EventReader<HookFiredEvent>,
+    mut player_query: Query<(&mut Animator, &PlayerBody), With<Player>>,
+) {
+    let hook_fired = hook_fired_events.iter().count() > 0;
+    let animations = match player_animations.get(&game_textures.player) {
+        Some(animations) => animations,
+        None => return,
+    };
+
+    if let Ok((mut animator, player_body)) = player_query.get_single_mut() {
+        let shooting = animator.state == AnimationState::Shoot
+            && animations
+                .get(AnimationState::Shoot)
+                .is_some_and(|shoot| !shoot.is_finished(animator.elapsed));
+
+        if hook_fired {
+            *animator = Animator::new(AnimationState::Shoot);
+        } else if !shooting {
+            animator.set(if player_body.climbing {
+                AnimationState::Climb
+            } else if player_body.run < 0. {
+                AnimationState::RunLeft
+            } else if player_body.run > 0. {
+                AnimationState::RunRight
+            } else {
+                AnimationState::Idle
+            });
+        }
+    }
+}
+
+// The player is gone as soon as it's hit, a corpse plays the death animation in its place.
+fn player_death_system(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    player_animations: Res<Assets<PlayerAnimations>>,
+    player_atlas: Option<Res<PlayerAtlas>>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    corpse_query: Query<(Entity, &Animator), With<PlayerCorpse>>,
+) {
+    if let Some(player_atlas) = player_atlas {
+        for player_hit in player_hit_events.iter() {
+            commands.spawn((
+                SpriteSheetBundle {
+                    texture_atlas: player_atlas.atlas.clone(),
+                    transform: Transform {
+                        translation: player_hit.position.extend(0.),
+                        scale: Vec3::new(PLAYER_SCALE, PLAYER_SCALE, 1.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Animator::new(AnimationState::Death),
+                PlayerCorpse,
+                GameScreen,
+            ));
+        }
+    }
+
+    if let Some(animations) = player_animations.get(&game_textures.player) {
+        for (corpse_entity, animator) in &corpse_query {
+            let finished = animations
+                .get(animator.state)
+                .is_none_or(|animation| animation.is_finished(animator.elapsed));
+            if finished {
+                commands.entity(corpse_entity).despawn();
+            }
+        }
+    }
+}
+
+fn animation_system(
+    game_textures: Res<GameTextures>,
+    player_animations: Res<Assets<PlayerAnimations>>,
+    mut query: Query<(&mut Animator, &mut TextureAtlasSprite)>,
+) {
+    let animations = match player_animations.get(&game_textures.player) {
+        Some(animations) => animations,
+        None => return,
+    };
+
+    for (mut animator, mut sprite) in &mut query {
+        animator.elapsed += TIME_STEP;
+        if let Some(animation) = animations.get(animator.state) {
+            sprite.index = animation.frame(animator.elapsed);
+            sprite.flip_x = animation.flip_x;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A sheet of 2 by 2 frames with a single animation.
+    fn animations(animation: &str) -> PlayerAnimations {
+        ron::from_str(&format!(
+            "(texture: \"sheet.png\", frame_size: (10., 10.), columns: 2, rows: 2, animations: {{ Idle: {} }})",
+            animation
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn the_shipped_animations_are_valid() {
+        let animations: PlayerAnimations =
+            ron::from_str(include_str!("../../assets/player.anim.ron")).unwrap();
+        assert!(animations.validate().is_ok());
+    }
+
+    #[test]
+    fn frames_on_the_sheet_are_valid() {
+        assert!(animations("(frames: [0, 3], frame_time: 0.1)")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn animations_without_frames_are_rejected() {
+        assert!(animations("(frames: [], frame_time: 0.1)")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn frames_need_a_time_to_show() {
+        assert!(animations("(frames: [0], frame_time: 0.)")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn frames_off_the_sheet_are_rejected() {
+        assert!(animations("(frames: [0, 4], frame_time: 0.1)")
+            .validate()
+            .is_err());
+    }
+}
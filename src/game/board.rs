@@ -1,23 +1,26 @@
 use bevy::prelude::*;
 
-use crate::game::{Wall, BOTTOM, LEFT, RIGHT, TOP, WALL_COLOR, WALL_SIZE};
-use crate::{AppState, Fonts};
+use crate::game::theme::{Palette, ThemeImages, ThemeLabel};
+use crate::game::{BubbleState, Wall, BACKGROUND_Z, BOTTOM, LEFT, RIGHT, TOP, WALL_SIZE};
+use crate::{AppState, GameMode};
 
-use super::components::{GameScreen, LivesText, ScoreText};
-use super::{
-    LIVES_TEXT_COLOR, LIVES_TEXT_DEFAULT, LIVES_TEXT_SIZE, LIVES_TEXT_X, LIVES_TEXT_Y,
-    SCORE_TEXT_COLOR, SCORE_TEXT_DEFAULT, SCORE_TEXT_SIZE, SCORE_TEXT_X, SCORE_TEXT_Y,
+use super::components::{
+    Breakable, Ceiling, Conveyor, GameScreen, Glass, Ladder, Movement, Platform, Waypoints,
 };
+use super::level::{Level, LevelHandles, ObstacleKind};
+use super::{GLASS_Z, LADDER_Z};
 pub struct BoardPlugin;
 
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
             SystemSet::on_enter(AppState::Game)
-                .with_system(add_walls_system)
-                .with_system(score_system)
-                .with_system(lives_system),
-        );
+                .with_system(background_system.after(ThemeLabel))
+                .with_system(add_walls_system.after(ThemeLabel))
+                .with_system(obstacles_system.after(ThemeLabel)),
+        )
+        // A level that wasn't loaded yet on entering the game is built once it is.
+        .add_system_set(SystemSet::on_update(AppState::Game).with_system(obstacles_system));
     }
 }
 
@@ -58,90 +61,143 @@ struct WallBundle {
 }
 
 impl WallBundle {
-    fn new(location: WallLocation) -> WallBundle {
+    fn new(location: WallLocation, palette: &Palette, theme_images: &ThemeImages) -> WallBundle {
         WallBundle {
-            sprite_bundle: SpriteBundle {
-                transform: Transform {
-                    translation: location.position().extend(0.0),
-                    scale: location.size().extend(1.0),
-                    ..default()
-                },
-                sprite: Sprite {
-                    color: WALL_COLOR,
-                    ..default()
-                },
-                ..default()
-            },
+            sprite_bundle: solid_sprite(
+                location.position().extend(0.0),
+                location.size(),
+                palette.wall,
+                theme_images.wall.as_ref(),
+            ),
             wall: Wall,
         }
     }
 }
 
-fn add_walls_system(mut commands: Commands) {
-    commands.spawn((WallBundle::new(WallLocation::Left), GameScreen));
-    commands.spawn((WallBundle::new(WallLocation::Right), GameScreen));
-    commands.spawn((WallBundle::new(WallLocation::Bottom), GameScreen));
-    commands.spawn((WallBundle::new(WallLocation::Top), GameScreen));
+// Sprite of a solid block, the wall texture is stretched over it and tinted with its colour.
+fn solid_sprite(
+    translation: Vec3,
+    size: Vec2,
+    color: Color,
+    texture: Option<&Handle<Image>>,
+) -> SpriteBundle {
+    SpriteBundle {
+        transform: Transform {
+            translation,
+            scale: size.extend(1.0),
+            ..default()
+        },
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::ONE),
+            ..default()
+        },
+        texture: texture.cloned().unwrap_or_default(),
+        ..default()
+    }
 }
 
-fn score_system(mut commands: Commands, fonts: Res<Fonts>) {
-    let score_text_style = TextStyle {
-        font: fonts.default.clone(),
-        font_size: SCORE_TEXT_SIZE,
-        color: SCORE_TEXT_COLOR,
-    };
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    position: UiRect {
-                        left: Val::Px(SCORE_TEXT_X),
-                        bottom: Val::Px(SCORE_TEXT_Y),
-                        ..default()
-                    },
-                    margin: UiRect::all(Val::Auto),
-                    align_items: AlignItems::Center,
+fn background_system(mut commands: Commands, theme_images: Res<ThemeImages>) {
+    if let Some(background) = &theme_images.background {
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., 0., BACKGROUND_Z),
+                    scale: Vec3::new(RIGHT - LEFT, TOP - BOTTOM, 1.),
                     ..default()
                 },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::ONE),
+                    ..default()
+                },
+                texture: background.clone(),
                 ..default()
             },
             GameScreen,
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                TextBundle::from_section(SCORE_TEXT_DEFAULT.to_string(), score_text_style),
-                ScoreText,
-            ));
-        });
+        ));
+    }
 }
 
-fn lives_system(mut commands: Commands, fonts: Res<Fonts>) {
-    let score_text_style = TextStyle {
-        font: fonts.default.clone(),
-        font_size: LIVES_TEXT_SIZE,
-        color: LIVES_TEXT_COLOR,
-    };
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    position: UiRect {
-                        left: Val::Px(LIVES_TEXT_X),
-                        bottom: Val::Px(LIVES_TEXT_Y),
-                        ..default()
-                    },
-                    margin: UiRect::all(Val::Auto),
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                ..default()
-            },
+fn add_walls_system(mut commands: Commands, palette: Res<Palette>, theme_images: Res<ThemeImages>) {
+    for location in [
+        WallLocation::Left,
+        WallLocation::Right,
+        WallLocation::Bottom,
+    ] {
+        commands.spawn((
+            WallBundle::new(location, &palette, &theme_images),
             GameScreen,
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                TextBundle::from_section(LIVES_TEXT_DEFAULT.to_string(), score_text_style),
-                LivesText,
+        ));
+    }
+    commands.spawn((
+        WallBundle::new(WallLocation::Top, &palette, &theme_images),
+        Ceiling,
+        GameScreen,
+    ));
+}
+
+fn obstacles_system(
+    mut commands: Commands,
+    mut bubble_state: ResMut<BubbleState>,
+    levels: Res<Assets<Level>>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+    palette: Res<Palette>,
+    theme_images: Res<ThemeImages>,
+) {
+    if bubble_state.obstacles_spawned {
+        return;
+    }
+    if let Some(level) = levels.get(level_handles.current(*game_mode)) {
+        bubble_state.obstacles_spawned = true;
+        for obstacle in level.obstacles.iter() {
+            // Only the solid blocks get the wall texture.
+            let (color, z, texture) = match obstacle.kind {
+                ObstacleKind::Platform => (palette.wall, 0., theme_images.wall.as_ref()),
+                ObstacleKind::Breakable => (palette.breakable, 0., theme_images.wall.as_ref()),
+                ObstacleKind::Glass => (palette.glass, GLASS_Z, None),
+                ObstacleKind::Ladder => (palette.ladder, LADDER_Z, None),
+                ObstacleKind::Conveyor => (palette.conveyor, 0., theme_images.wall.as_ref()),
+            };
+            let mut obstacle_entity = commands.spawn((
+                solid_sprite(
+                    Vec3::new(obstacle.position.0, obstacle.position.1, z),
+                    Vec2::new(obstacle.size.0, obstacle.size.1),
+                    color,
+                    texture,
+                ),
+                GameScreen,
             ));
-        });
+            match obstacle.kind {
+                ObstacleKind::Platform => obstacle_entity.insert((Wall, Platform)),
+                ObstacleKind::Breakable => obstacle_entity.insert((Wall, Breakable)),
+                ObstacleKind::Glass => obstacle_entity.insert((Wall, Glass)),
+                ObstacleKind::Ladder => obstacle_entity.insert(Ladder),
+                ObstacleKind::Conveyor => obstacle_entity.insert((
+                    Wall,
+                    Conveyor {
+                        speed: obstacle.speed,
+                    },
+                )),
+            };
+            if !obstacle.waypoints.is_empty() {
+                obstacle_entity.insert((
+                    Waypoints {
+                        points: obstacle
+                            .waypoints
+                            .iter()
+                            .map(|&(x, y)| Vec2::new(x, y))
+                            .collect(),
+                        next: 0,
+                        speed: obstacle.speed,
+                    },
+                    Movement {
+                        v_x: 0.,
+                        v_y: 0.,
+                        a: 0.,
+                    },
+                ));
+            }
+        }
+    }
 }
@@ -1,16 +1,43 @@
-use crate::game::components::{Bubble, BubbleSize, GameScreen, Movement};
-use crate::game::{BubbleState, BALL_COLOR, BALL_RADIUS, BALL_SLOWDOWN, BALL_SPEED_X, TIME_STEP};
-use crate::AppState;
+use crate::game::components::{
+    Bubble, BubbleKind, BubbleSize, GameScreen, Movement, PowerUpKind, Reward, RewardScore,
+};
+use crate::game::config::{BubbleConfig, ConfigLabel, GameConfig};
+use crate::game::events::BubblePoppedEvent;
+use crate::game::level::{Level, LevelHandles};
+use crate::game::theme::{Palette, ThemeLabel};
+use crate::game::{
+    BubbleState, GameRng, SimulationStage, TickLabel, BALL_RADIUS, BUBBLE_OUTLINE_WIDTH,
+    BUBBLE_SHADER_FILE, BUBBLE_SIZE_SHADING, BUBBLE_SIZE_SHADING_MIN, LEFT, REWARD_SIZE, RIGHT,
+    TIME_STEP, TOP, WALL_SIZE,
+};
+use crate::{AppState, GameMode};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bevy::sprite::MaterialMesh2dBundle;
+use bevy::reflect::TypeUuid;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle};
 use bevy::time::FixedTimestep;
+use rand::Rng;
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
 pub struct BubblePlugin;
 
 impl Plugin for BubblePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(BubbleState::default())
-            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(bubble_spawn_system))
+        app.add_plugin(Material2dPlugin::<BubbleMaterial>::default())
+            .insert_resource(BubbleState::default())
+            .insert_resource(BubbleCache::default())
+            .add_system_set(
+                SystemSet::on_enter(AppState::Game)
+                    .with_system(bubble_spawn_system.after(ThemeLabel).after(ConfigLabel)),
+            )
+            // A level that wasn't loaded yet on entering the game is spawned once it is.
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(bubble_spawn_system))
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Reactions.set().with_system(bubble_pop_system),
+            )
             .add_system_set(
                 SystemSet::on_update(AppState::Game)
                     .with_run_criteria(FixedTimestep::step(TIME_STEP as f64)),
@@ -18,38 +45,279 @@ impl Plugin for BubblePlugin {
     }
 }
 
+// Shaded instead of flat, darker at the rim with a highlight on top.
+#[derive(AsBindGroup, TypeUuid, Clone, PartialEq)]
+#[uuid = "5e1f8d2c-7b3a-4c69-b0e4-8a9d6f1c2e57"]
+pub struct BubbleMaterial {
+    #[uniform(0)]
+    color: Color,
+    #[uniform(0)]
+    shine: Color,
+    #[uniform(0)]
+    outline: Color,
+    // Part of the radius covered by the outline.
+    #[uniform(0)]
+    outline_width: f32,
+}
+
+impl BubbleMaterial {
+    // Bigger bubbles are drawn darker, so their size can be told at a glance.
+    fn new(palette: &Palette, kind: BubbleKind, size: f32) -> Self {
+        let shade = (1. - BUBBLE_SIZE_SHADING * (size - 1.)).max(BUBBLE_SIZE_SHADING_MIN);
+        let color = palette.bubble(kind);
+        Self {
+            color: Color::rgba(
+                color.r() * shade,
+                color.g() * shade,
+                color.b() * shade,
+                color.a(),
+            ),
+            shine: palette.shine,
+            outline: palette.outline,
+            outline_width: BUBBLE_OUTLINE_WIDTH / (BALL_RADIUS * size / 2.),
+        }
+    }
+}
+
+// Meshes and materials shared by all bubbles, rewards and power-ups, so spawning doesn't
+// allocate new ones.
+#[derive(Resource, Default)]
+pub struct BubbleCache {
+    circle: Handle<Mesh>,
+    quad: Handle<Mesh>,
+    materials: HashMap<(BubbleKind, u32), Handle<BubbleMaterial>>,
+    reward_material: Handle<ColorMaterial>,
+    power_up_materials: HashMap<PowerUpKind, Handle<ColorMaterial>>,
+}
+
+#[derive(SystemParam)]
+pub struct BubbleAssets<'w, 's> {
+    cache: ResMut<'w, BubbleCache>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<BubbleMaterial>>,
+    color_materials: ResMut<'w, Assets<ColorMaterial>>,
+    palette: Res<'w, Palette>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> BubbleAssets<'w, 's> {
+    pub fn circle(&mut self) -> Handle<Mesh> {
+        if self.cache.circle == Handle::default() {
+            self.cache.circle = self.meshes.add(shape::Circle::default().into());
+        }
+        self.cache.circle.clone()
+    }
+
+    pub fn quad(&mut self) -> Handle<Mesh> {
+        if self.cache.quad == Handle::default() {
+            self.cache.quad = self.meshes.add(shape::Quad::default().into());
+        }
+        self.cache.quad.clone()
+    }
+
+    // A material that no longer matches the palette, after a theme change, is updated in place.
+    pub fn material(&mut self, kind: BubbleKind, size: f32) -> Handle<BubbleMaterial> {
+        let material = BubbleMaterial::new(&self.palette, kind, size);
+        let handle = match self.cache.materials.get(&(kind, size as u32)) {
+            Some(handle) => handle.clone(),
+            None => {
+                let handle = self.materials.add(material.clone());
+                self.cache
+                    .materials
+                    .insert((kind, size as u32), handle.clone());
+                handle
+            }
+        };
+        if self.materials.get(&handle) != Some(&material) {
+            if let Some(existing) = self.materials.get_mut(&handle) {
+                *existing = material;
+            }
+        }
+        handle
+    }
+
+    fn reward_material(&mut self) -> Handle<ColorMaterial> {
+        let color = self.palette.reward;
+        match self.color_materials.get_mut(&self.cache.reward_material) {
+            Some(material) if material.color == color => {}
+            Some(material) => material.color = color,
+            None => self.cache.reward_material = self.color_materials.add(color.into()),
+        }
+        self.cache.reward_material.clone()
+    }
+
+    pub fn power_up_material(&mut self, kind: PowerUpKind) -> Handle<ColorMaterial> {
+        let color = self.palette.power_up(kind);
+        let handle = self
+            .cache
+            .power_up_materials
+            .get(&kind)
+            .cloned()
+            .unwrap_or_default();
+        match self.color_materials.get_mut(&handle) {
+            Some(material) if material.color == color => handle,
+            Some(material) => {
+                material.color = color;
+                handle
+            }
+            None => {
+                let handle = self.color_materials.add(color.into());
+                self.cache.power_up_materials.insert(kind, handle.clone());
+                handle
+            }
+        }
+    }
+}
+
+impl Material2d for BubbleMaterial {
+    fn fragment_shader() -> ShaderRef {
+        BUBBLE_SHADER_FILE.into()
+    }
+}
+
+pub fn spawn_bubble(
+    commands: &mut Commands,
+    bubble_assets: &mut BubbleAssets,
+    config: &BubbleConfig,
+    position: Vec2,
+    size: f32,
+    kind: BubbleKind,
+    velocity: Vec2,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: bubble_assets.circle().into(),
+            material: bubble_assets.material(kind, size),
+            transform: Transform {
+                translation: position.extend(0.),
+                scale: Vec3::new(BALL_RADIUS * size, BALL_RADIUS * size, 0.),
+                ..default()
+            },
+            ..default()
+        },
+        Bubble,
+        GameScreen,
+        Movement {
+            v_x: velocity.x,
+            v_y: velocity.y,
+            a: config.slowdown,
+        },
+        BubbleSize { size },
+        kind,
+    ));
+}
+
+// Drops a bubble in from a random spot under the ceiling, used by the endless modes.
+pub fn drop_bubble(
+    commands: &mut Commands,
+    bubble_assets: &mut BubbleAssets,
+    config: &BubbleConfig,
+    rng: &mut GameRng,
+    size: f32,
+    kind: BubbleKind,
+    speed: f32,
+) {
+    let radius = BALL_RADIUS * size / 2.;
+    let position = Vec2::new(
+        rng.gen_range(LEFT + WALL_SIZE / 2. + radius..RIGHT - WALL_SIZE / 2. - radius),
+        TOP - WALL_SIZE / 2. - radius - 1.,
+    );
+    let direction = if rng.gen_bool(0.5) { -1. } else { 1. };
+
+    spawn_bubble(
+        commands,
+        bubble_assets,
+        config,
+        position,
+        size,
+        kind,
+        Vec2::new(direction * config.speed(kind) * speed, 0.),
+    );
+}
+
 fn bubble_spawn_system(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut bubble_assets: BubbleAssets,
     mut bubble_state: ResMut<BubbleState>,
+    levels: Res<Assets<Level>>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+    config: Res<GameConfig>,
 ) {
     if !bubble_state.spawned {
-        for i in 0..bubble_state.count {
-            let (bubble_position_x, bubble_position_y) = *bubble_state.positions.get(i).unwrap();
-            let bubble_size = *bubble_state.sizes.get(i).unwrap();
-            commands.spawn((
-                MaterialMesh2dBundle {
-                    mesh: meshes.add(shape::Circle::default().into()).into(),
-                    material: materials.add(ColorMaterial::from(BALL_COLOR)),
-                    transform: Transform {
-                        translation: Vec3::new(bubble_position_x, bubble_position_y, 0.),
-                        scale: Vec3::new(BALL_RADIUS * bubble_size, BALL_RADIUS * bubble_size, 0.),
-                        ..default()
-                    },
-                    ..default()
-                },
-                Bubble,
-                GameScreen,
-                Movement {
-                    v_x: BALL_SPEED_X,
-                    v_y: BALL_SPEED_X * bubble_size,
-                    a: BALL_SLOWDOWN,
-                },
-                BubbleSize { size: bubble_size },
-            ));
+        if let Some(level) = levels.get(level_handles.current(*game_mode)) {
+            for bubble in level.bubbles.iter() {
+                spawn_bubble(
+                    &mut commands,
+                    &mut bubble_assets,
+                    &config.bubble,
+                    Vec2::new(bubble.position.0, bubble.position.1),
+                    bubble.size,
+                    bubble.kind,
+                    Vec2::new(
+                        bubble.direction.sign() * config.bubble.speed(bubble.kind),
+                        config.bubble.bounce(bubble.kind, bubble.size),
+                    ),
+                );
+            }
+
+            bubble_state.spawn(level.bubbles.len());
+        }
+    }
+}
+
+// Splits a popped bubble in two and drops a reward where it was.
+pub fn bubble_pop_system(
+    mut commands: Commands,
+    mut bubble_assets: BubbleAssets,
+    mut bubble_state: ResMut<BubbleState>,
+    config: Res<GameConfig>,
+    mut bubble_popped_events: EventReader<BubblePoppedEvent>,
+) {
+    for bubble_popped in bubble_popped_events.iter() {
+        bubble_state.pop();
+
+        if bubble_popped.size > 2. {
+            bubble_state.count += 2;
+            let new_bubble_size = bubble_popped.size - 1.;
+            for direction in [-1., 1.] {
+                spawn_bubble(
+                    &mut commands,
+                    &mut bubble_assets,
+                    &config.bubble,
+                    bubble_popped.position,
+                    new_bubble_size,
+                    bubble_popped.kind,
+                    Vec2::new(
+                        direction * bubble_popped.speed,
+                        config.bubble.bounce(bubble_popped.kind, new_bubble_size),
+                    ),
+                );
+            }
         }
 
-        bubble_state.spawn();
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: bubble_assets.quad().into(),
+                material: bubble_assets.reward_material(),
+                transform: Transform {
+                    translation: bubble_popped.position.extend(0.),
+                    scale: Vec3::new(REWARD_SIZE, REWARD_SIZE, 0.),
+                    ..default()
+                },
+                ..default()
+            },
+            Movement {
+                v_x: 0.,
+                v_y: -config.bubble.reward_speed,
+                a: 0.,
+            },
+            Reward,
+            RewardScore {
+                score: config.bubble.reward_score / (bubble_popped.size as usize).max(1),
+            },
+            GameScreen,
+        ));
     }
 }
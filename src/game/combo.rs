@@ -0,0 +1,196 @@
+use crate::game::components::{FloatingText, GameScreen};
+use crate::game::config::{ComboConfig, ConfigLabel, GameConfig};
+use crate::game::events::{HookMissedEvent, PlayerHitEvent};
+use crate::game::particles::particle_system;
+use crate::game::{
+    scoring_system, SimulationStage, TickLabel, FLOATING_TEXT_COLOR, FLOATING_TEXT_SIZE,
+    FLOATING_TEXT_SPEED, FLOATING_TEXT_TIME, TIME_STEP,
+};
+use crate::{AppState, Fonts};
+use bevy::prelude::*;
+use std::time::Duration;
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Game).with_system(combo_system.after(ConfigLabel)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Movement
+                .set()
+                .with_system(combo_timer_system)
+                .with_system(floating_text_system.after(particle_system)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Reactions
+                .set()
+                .with_system(combo_reset_system.before(scoring_system)),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct Combo {
+    pub multiplier: usize,
+    chain: usize,
+    window: Timer,
+}
+
+impl Combo {
+    fn new(config: &ComboConfig) -> Self {
+        let mut window = Timer::from_seconds(config.window, TimerMode::Once);
+        window.tick(window.duration());
+        Self {
+            multiplier: 1,
+            chain: 0,
+            window,
+        }
+    }
+
+    // Counts a pop and returns the multiplier it scores with. The multiplier grows
+    // on pops in quick succession and on every few pops in a row without a miss.
+    pub fn pop(&mut self, config: &ComboConfig) -> usize {
+        self.chain += 1;
+        if !self.window.finished() || self.chain.is_multiple_of(config.chain_step) {
+            self.multiplier = (self.multiplier + 1).min(config.max);
+        }
+        self.window.reset();
+        self.multiplier
+    }
+
+    pub fn reset(&mut self, config: &ComboConfig) {
+        *self = Self::new(config);
+    }
+}
+
+fn combo_system(mut commands: Commands, config: Res<GameConfig>) {
+    commands.insert_resource(Combo::new(&config.combo));
+}
+
+fn combo_timer_system(mut combo: ResMut<Combo>) {
+    // Bypass change detection, the HUD only cares about the multiplier.
+    combo
+        .bypass_change_detection()
+        .window
+        .tick(Duration::from_secs_f32(TIME_STEP));
+}
+
+// Getting hit or the hook reaching the ceiling without popping anything breaks the chain.
+fn combo_reset_system(
+    mut combo: ResMut<Combo>,
+    config: Res<GameConfig>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+    mut hook_missed_events: EventReader<HookMissedEvent>,
+) {
+    let player_hit = player_hit_events.iter().count() > 0;
+    let hook_missed = hook_missed_events
+        .iter()
+        .any(|hook_missed| hook_missed.ceiling);
+    if player_hit || hook_missed {
+        combo.reset(&config.combo);
+    }
+}
+
+pub fn spawn_floating_text(commands: &mut Commands, fonts: &Fonts, position: Vec2, value: String) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                value,
+                TextStyle {
+                    font: fonts.default.clone(),
+                    font_size: FLOATING_TEXT_SIZE,
+                    color: FLOATING_TEXT_COLOR,
+                },
+            )
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_translation(position.extend(1.)),
+            ..default()
+        },
+        FloatingText {
+            timer: Timer::from_seconds(FLOATING_TEXT_TIME, TimerMode::Once),
+        },
+        GameScreen,
+    ));
+}
+
+fn floating_text_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Text, &mut FloatingText)>,
+) {
+    for (entity, mut transform, mut text, mut floating_text) in &mut query {
+        floating_text.timer.tick(Duration::from_secs_f32(TIME_STEP));
+        if floating_text.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation.y += FLOATING_TEXT_SPEED * TIME_STEP;
+        let alpha = floating_text.timer.percent_left();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ComboConfig {
+        ComboConfig {
+            window: 1.,
+            chain_step: 3,
+            max: 4,
+        }
+    }
+
+    fn wait(combo: &mut Combo, seconds: f32) {
+        combo.window.tick(Duration::from_secs_f32(seconds));
+    }
+
+    #[test]
+    fn quick_pops_raise_the_multiplier() {
+        let config = config();
+        let mut combo = Combo::new(&config);
+        assert_eq!(combo.pop(&config), 1);
+        wait(&mut combo, 0.5);
+        assert_eq!(combo.pop(&config), 2);
+    }
+
+    #[test]
+    fn slow_pops_raise_it_every_few_in_a_row() {
+        let config = config();
+        let mut combo = Combo::new(&config);
+        let mut multipliers = Vec::new();
+        for _ in 0..6 {
+            multipliers.push(combo.pop(&config));
+            wait(&mut combo, 2.);
+        }
+        assert_eq!(multipliers, vec![1, 1, 2, 2, 2, 3]);
+    }
+
+    #[test]
+    fn the_multiplier_stops_at_the_max() {
+        let config = config();
+        let mut combo = Combo::new(&config);
+        for _ in 0..10 {
+            combo.pop(&config);
+        }
+        assert_eq!(combo.multiplier, config.max);
+    }
+
+    #[test]
+    fn reset_breaks_the_chain() {
+        let config = config();
+        let mut combo = Combo::new(&config);
+        combo.pop(&config);
+        combo.pop(&config);
+        combo.reset(&config);
+        assert_eq!(combo.multiplier, 1);
+        assert_eq!(combo.pop(&config), 1);
+    }
+}
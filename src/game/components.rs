@@ -1,4 +1,5 @@
-use bevy::prelude::Component;
+use bevy::prelude::{Component, Entity, Timer, Vec2};
+use serde::{Deserialize, Serialize};
 
 #[derive(Component)]
 pub struct GameScreen;
@@ -12,17 +13,124 @@ pub struct Hook;
 #[derive(Component)]
 pub struct Wall;
 
+#[derive(Component)]
+pub struct Ceiling;
+
+#[derive(Component)]
+pub struct Platform;
+
+#[derive(Component)]
+pub struct Breakable;
+
+#[derive(Component)]
+pub struct Glass;
+
+#[derive(Component)]
+pub struct Ladder;
+
+#[derive(Component)]
+pub struct Conveyor {
+    pub speed: f32,
+}
+
+#[derive(Component)]
+pub struct Waypoints {
+    pub points: Vec<Vec2>,
+    pub next: usize,
+    pub speed: f32,
+}
+
 #[derive(Component)]
 pub struct Bubble;
 
 #[derive(Component)]
 pub struct Reward;
 
+#[derive(Component)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub lifetime: Timer,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PowerUpKind {
+    // Stops the wave timer.
+    Clock,
+    // Runs the wave timer at half speed.
+    Hourglass,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 2] = [PowerUpKind::Clock, PowerUpKind::Hourglass];
+}
+
 #[derive(Component)]
 pub struct ScoreText;
 
 #[derive(Component)]
-pub struct LivesText;
+pub struct Hearts;
+
+#[derive(Component)]
+pub struct ComboText;
+
+#[derive(Component)]
+pub struct LevelText;
+
+#[derive(Component)]
+pub struct TimerText;
+
+#[derive(Component)]
+pub struct WeaponText;
+
+#[derive(Component)]
+pub struct ReplayText;
+
+#[derive(Component)]
+pub struct GhostText;
+
+// The translucent player of the run raced against.
+#[derive(Component)]
+pub struct GhostPlayer;
+
+#[derive(Component)]
+pub struct PowerUpSlot {
+    pub kind: PowerUpKind,
+}
+
+#[derive(Component)]
+pub struct PowerUpTimerText {
+    pub kind: PowerUpKind,
+}
+
+#[derive(Component)]
+pub struct PanicMeter;
+
+#[derive(Component)]
+pub struct DebugText;
+
+#[derive(Component)]
+pub struct SimulationText;
+
+#[derive(Component)]
+pub struct ConsoleScreen;
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+// Line of the debug overlay, kept around and reused from frame to frame.
+#[derive(Component)]
+pub struct DebugShape;
+
+#[derive(Component)]
+pub struct FloatingText {
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct Particle {
+    pub velocity: Vec2,
+    pub lifetime: Timer,
+}
 
 #[derive(Component)]
 pub struct RewardScore {
@@ -34,9 +142,61 @@ pub struct BubbleSize {
     pub size: f32,
 }
 
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum BubbleKind {
+    #[default]
+    Normal,
+    Fast,
+    Bouncy,
+}
+
 #[derive(Component)]
 pub struct Movement {
     pub v_x: f32,
     pub v_y: f32,
     pub a: f32,
 }
+
+#[derive(Component, Default)]
+pub struct PlayerBody {
+    pub v_y: f32,
+    pub ground: Option<Entity>,
+    // Horizontal input of the last step, -1, 0 or 1.
+    pub run: f32,
+    pub climbing: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum AnimationState {
+    #[default]
+    Idle,
+    RunLeft,
+    RunRight,
+    Shoot,
+    Climb,
+    Death,
+}
+
+#[derive(Component, Default)]
+pub struct Animator {
+    pub state: AnimationState,
+    // Seconds since the current animation started.
+    pub elapsed: f32,
+}
+
+impl Animator {
+    pub fn new(state: AnimationState) -> Self {
+        Self { state, elapsed: 0. }
+    }
+
+    // Switching to the running animation again doesn't restart it.
+    pub fn set(&mut self, state: AnimationState) {
+        if self.state != state {
+            *self = Animator::new(state);
+        }
+    }
+}
+
+// What's left of the player after a hit, removed once its animation ends.
+#[derive(Component)]
+pub struct PlayerCorpse;
@@ -0,0 +1,539 @@
+use crate::game::components::{BubbleKind, PowerUpKind};
+use crate::game::level::{Level, LevelHandles};
+use crate::game::replay::{Recording, ReplayPlayback};
+use crate::game::CONFIG_FILE;
+use crate::{AppState, GameMode};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use ron::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<GameConfig>()
+            .init_asset_loader::<GameConfigLoader>()
+            .insert_resource(GameConfig::default())
+            .add_startup_system(config_handle_system)
+            .add_system_set(
+                SystemSet::on_enter(AppState::Game).with_system(config_system.label(ConfigLabel)),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(config_reload_system));
+    }
+}
+
+// Systems reading the config on entering the game run after it is set up for the round.
+#[derive(SystemLabel)]
+pub struct ConfigLabel;
+
+// Gameplay tuning read from `CONFIG_FILE`, edits to it apply while the game is running. As a
+// resource it holds the values of the current round, with the overrides of its mode and level.
+// Anything left out of the file keeps its default.
+#[derive(Resource, Serialize, Deserialize, TypeUuid, Clone)]
+#[uuid = "2d7c4e91-58a3-4f0b-9c6e-b14a7f3d8e25"]
+#[serde(default)]
+pub struct GameConfig {
+    pub lives: usize,
+    // Extra lives with the assist option on.
+    pub assist_lives: usize,
+    pub player: PlayerConfig,
+    pub bubble: BubbleConfig,
+    pub combo: ComboConfig,
+    pub survival: SurvivalConfig,
+    pub panic: PanicConfig,
+    pub power_up: PowerUpConfig,
+    // Values changed in a single mode, written like the rest of the file.
+    #[serde(skip_serializing)]
+    modes: HashMap<GameMode, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PlayerConfig {
+    pub speed: f32,
+    pub climb_speed: f32,
+    pub gravity: f32,
+    pub max_fall_speed: f32,
+    // How far below a surface the player's feet may be and still count as standing on it.
+    pub ground_snap: f32,
+    pub hook_speed: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BubbleConfig {
+    pub speed_x: f32,
+    pub slowdown: f32,
+    // Horizontal speed multiplier of the fast bubbles.
+    pub fast_speed: f32,
+    // Bounce height multiplier of the bouncy bubbles.
+    pub bouncy_bounce: f32,
+    // Score of popping the smallest bubble, bigger ones score less.
+    pub pop_score: usize,
+    pub reward_speed: f32,
+    pub reward_score: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ComboConfig {
+    // Pops within this many seconds of each other raise the multiplier.
+    pub window: f32,
+    // Every this many pops without a miss or a hit also raises it.
+    pub chain_step: usize,
+    pub max: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SurvivalConfig {
+    pub first_drop: f32,
+    pub interval_start: f32,
+    pub interval_decay: f32,
+    pub interval_min: f32,
+    pub speed_step: f32,
+    pub speed_max: f32,
+    // Every this many waves the biggest dropped bubble grows by one size.
+    pub size_waves: usize,
+    pub size_max: usize,
+    pub fast_wave: usize,
+    pub bouncy_wave: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PanicConfig {
+    pub first_drop: f32,
+    pub interval_start: f32,
+    pub interval_decay: f32,
+    pub interval_min: f32,
+    pub speed_step: f32,
+    pub pops_per_level: usize,
+    pub size_levels: usize,
+    pub size_max: usize,
+    pub fast_level: usize,
+    pub bouncy_level: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PowerUpConfig {
+    // Chance of a popped bubble dropping a clock or an hourglass in panic mode.
+    pub chance: f64,
+    pub speed: f32,
+    pub lifetime: f32,
+    pub clock_time: f32,
+    pub hourglass_time: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            lives: 3,
+            assist_lives: 2,
+            player: PlayerConfig::default(),
+            bubble: BubbleConfig::default(),
+            combo: ComboConfig::default(),
+            survival: SurvivalConfig::default(),
+            panic: PanicConfig::default(),
+            power_up: PowerUpConfig::default(),
+            modes: HashMap::new(),
+        }
+    }
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            speed: 300.,
+            climb_speed: 200.,
+            gravity: 1200.,
+            max_fall_speed: 600.,
+            ground_snap: 5.,
+            hook_speed: 100.,
+        }
+    }
+}
+
+impl Default for BubbleConfig {
+    fn default() -> Self {
+        Self {
+            speed_x: 200.,
+            slowdown: 600.,
+            fast_speed: 1.5,
+            bouncy_bounce: 1.2,
+            pop_score: 600,
+            reward_speed: 300.,
+            reward_score: 1200,
+        }
+    }
+}
+
+impl Default for ComboConfig {
+    fn default() -> Self {
+        Self {
+            window: 1.5,
+            chain_step: 3,
+            max: 8,
+        }
+    }
+}
+
+impl Default for SurvivalConfig {
+    fn default() -> Self {
+        Self {
+            first_drop: 1.,
+            interval_start: 8.,
+            interval_decay: 0.92,
+            interval_min: 2.,
+            speed_step: 0.05,
+            speed_max: 2.,
+            size_waves: 4,
+            size_max: 4,
+            fast_wave: 5,
+            bouncy_wave: 10,
+        }
+    }
+}
+
+impl Default for PanicConfig {
+    fn default() -> Self {
+        Self {
+            first_drop: 1.,
+            interval_start: 4.,
+            interval_decay: 0.85,
+            interval_min: 0.75,
+            speed_step: 0.1,
+            pops_per_level: 10,
+            size_levels: 3,
+            size_max: 3,
+            fast_level: 3,
+            bouncy_level: 6,
+        }
+    }
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> Self {
+        Self {
+            chance: 0.08,
+            speed: 200.,
+            lifetime: 6.,
+            clock_time: 5.,
+            hourglass_time: 10.,
+        }
+    }
+}
+
+impl GameConfig {
+    // Catches the values the game would divide by zero, loop forever or panic on.
+    pub fn validate(&self) -> Result<(), String> {
+        let durations = [
+            ("combo.window", self.combo.window),
+            ("survival.first_drop", self.survival.first_drop),
+            ("survival.interval_start", self.survival.interval_start),
+            ("survival.interval_min", self.survival.interval_min),
+            ("panic.first_drop", self.panic.first_drop),
+            ("panic.interval_start", self.panic.interval_start),
+            ("panic.interval_min", self.panic.interval_min),
+            ("power_up.lifetime", self.power_up.lifetime),
+            ("power_up.clock_time", self.power_up.clock_time),
+            ("power_up.hourglass_time", self.power_up.hourglass_time),
+        ];
+        if let Some((name, seconds)) = durations
+            .into_iter()
+            .find(|(_, seconds)| !(seconds.is_finite() && *seconds >= 0.))
+        {
+            return Err(format!(
+                "{} is {} seconds, durations can't be negative",
+                name, seconds
+            ));
+        }
+        let steps = [
+            ("survival.size_waves", self.survival.size_waves),
+            ("panic.size_levels", self.panic.size_levels),
+            ("panic.pops_per_level", self.panic.pops_per_level),
+        ];
+        if let Some((name, _)) = steps.into_iter().find(|(_, step)| *step == 0) {
+            return Err(format!("{} is 0, it has to be at least 1", name));
+        }
+        // Dropped bubbles are at least size 2.
+        let sizes = [
+            ("survival.size_max", self.survival.size_max),
+            ("panic.size_max", self.panic.size_max),
+        ];
+        if let Some((name, size)) = sizes.into_iter().find(|(_, size)| *size < 2) {
+            return Err(format!("{} is {}, it has to be at least 2", name, size));
+        }
+        if !(0. ..=1.).contains(&self.power_up.chance) {
+            return Err(format!(
+                "power_up.chance is {}, chances are from 0 to 1",
+                self.power_up.chance
+            ));
+        }
+        Ok(())
+    }
+
+    // The config with the values of the mode and then of the level put over it.
+    fn with_overrides(&self, game_mode: GameMode, level: Option<&Level>) -> Result<Self, String> {
+        // Overrides are merged as values, so they can leave out any part of a section.
+        let mut config: Value = match ron::to_string(self)
+            .ok()
+            .and_then(|config| ron::from_str(&config).ok())
+        {
+            Some(config) => config,
+            None => return Ok(self.clone()),
+        };
+        for overrides in [
+            self.modes.get(&game_mode),
+            level.and_then(|level| level.config.as_ref()),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            merge(&mut config, overrides);
+        }
+        let config: Self = config
+            .into_rust()
+            .map_err(|error| format!("Invalid config overrides: {}", error))?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+// Replaces the values named in the overrides, nested sections keep the values left out.
+fn merge(config: &mut Value, overrides: &Value) {
+    match (config, overrides) {
+        (Value::Map(config), Value::Map(overrides)) => {
+            for (key, value) in overrides.iter() {
+                match config.iter_mut().find(|(config_key, _)| *config_key == key) {
+                    Some((_, config_value)) => merge(config_value, value),
+                    None => warn!("Ignoring unknown config override {:?}", key),
+                }
+            }
+        }
+        (config, overrides) => *config = overrides.clone(),
+    }
+}
+
+impl BubbleConfig {
+    // Horizontal speed of a bubble of the kind.
+    pub fn speed(&self, kind: BubbleKind) -> f32 {
+        match kind {
+            BubbleKind::Fast => self.speed_x * self.fast_speed,
+            _ => self.speed_x,
+        }
+    }
+
+    // Vertical speed a bubble bounces off the floor with, bigger ones bounce higher.
+    pub fn bounce(&self, kind: BubbleKind, size: f32) -> f32 {
+        match kind {
+            BubbleKind::Bouncy => self.speed_x * size * self.bouncy_bounce,
+            _ => self.speed_x * size,
+        }
+    }
+}
+
+impl PowerUpConfig {
+    pub fn duration(&self, kind: PowerUpKind) -> f32 {
+        match kind {
+            PowerUpKind::Clock => self.clock_time,
+            PowerUpKind::Hourglass => self.hourglass_time,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ConfigHandle(pub Handle<GameConfig>);
+
+// Everything the config of a round is made from.
+#[derive(SystemParam)]
+struct ConfigSources<'w, 's> {
+    configs: Res<'w, Assets<GameConfig>>,
+    config_handle: Res<'w, ConfigHandle>,
+    levels: Res<'w, Assets<Level>>,
+    level_handles: Res<'w, LevelHandles>,
+    game_mode: Res<'w, GameMode>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> ConfigSources<'w, 's> {
+    // A config file that isn't loaded yet or fails to load leaves the defaults in place.
+    fn file_config(&self) -> GameConfig {
+        self.configs
+            .get(&self.config_handle.0)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn config(&self) -> Result<GameConfig, String> {
+        let level = self.levels.get(self.level_handles.current(*self.game_mode));
+        self.file_config().with_overrides(*self.game_mode, level)
+    }
+}
+
+#[derive(Default)]
+struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let config = ron::de::from_bytes::<GameConfig>(bytes)?;
+            config.validate().map_err(bevy::asset::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["config.ron"]
+    }
+}
+
+fn config_handle_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ConfigHandle(asset_server.load(CONFIG_FILE)));
+}
+
+// Overrides that leave an invalid config are skipped for the round, a watched replay plays with
+// the config it was recorded with.
+fn config_system(
+    sources: ConfigSources,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut config: ResMut<GameConfig>,
+) {
+    let current = sources.config().unwrap_or_else(|error| {
+        warn!("Ignoring the config overrides: {}", error);
+        sources.file_config()
+    });
+    *config = match replay_playback {
+        Some(replay_playback) if replay_playback.is_watched() => {
+            replay_playback.replay.config_or(current)
+        }
+        _ => current,
+    };
+}
+
+// Applies edits to the config file or the level mid-round. The run stops being recorded first,
+// its replay wouldn't play out the same with the values changed partway, and a watched replay
+// keeps the config it was recorded with.
+fn config_reload_system(
+    mut commands: Commands,
+    sources: ConfigSources,
+    recording: Option<Res<Recording>>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut config: ResMut<GameConfig>,
+    mut config_events: EventReader<AssetEvent<GameConfig>>,
+    mut level_events: EventReader<AssetEvent<Level>>,
+) {
+    let config_changed = config_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    let level_changed = level_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    if !config_changed && !level_changed {
+        return;
+    }
+    if replay_playback.is_some_and(|playback| playback.is_watched()) {
+        info!("Keeping the config the replay was recorded with");
+        return;
+    }
+    match sources.config() {
+        Ok(reloaded) => {
+            info!("Reloading the game config");
+            if recording.is_some() {
+                info!("The run is no longer recorded");
+                commands.remove_resource::<Recording>();
+            }
+            *config = reloaded;
+        }
+        Err(error) => warn!("Keeping the previous config: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(file: &str) -> GameConfig {
+        ron::from_str(file).unwrap()
+    }
+
+    fn overriding_level(config: &str) -> Level {
+        Level {
+            config: Some(ron::from_str(config).unwrap()),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(GameConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn mode_overrides_keep_the_values_left_out() {
+        let config = config("(lives: 5, modes: { Survival: (lives: 1, survival: (size_max: 6)) })");
+
+        let survival = config.with_overrides(GameMode::Survival, None).unwrap();
+        assert_eq!(survival.lives, 1);
+        assert_eq!(survival.survival.size_max, 6);
+        assert_eq!(
+            survival.survival.size_waves,
+            SurvivalConfig::default().size_waves
+        );
+
+        let campaign = config.with_overrides(GameMode::Campaign, None).unwrap();
+        assert_eq!(campaign.lives, 5);
+        assert_eq!(
+            campaign.survival.size_max,
+            SurvivalConfig::default().size_max
+        );
+    }
+
+    #[test]
+    fn level_overrides_go_over_mode_overrides() {
+        let config = config("(modes: { Campaign: (lives: 1, assist_lives: 4) })");
+        let level = overriding_level("(lives: 2)");
+
+        let overridden = config
+            .with_overrides(GameMode::Campaign, Some(&level))
+            .unwrap();
+        assert_eq!(overridden.lives, 2);
+        assert_eq!(overridden.assist_lives, 4);
+    }
+
+    #[test]
+    fn unknown_overrides_are_ignored() {
+        let level = overriding_level("(lives: 2, jetpack: true)");
+
+        let overridden = GameConfig::default()
+            .with_overrides(GameMode::Campaign, Some(&level))
+            .unwrap();
+        assert_eq!(overridden.lives, 2);
+    }
+
+    #[test]
+    fn invalid_overrides_are_rejected() {
+        let level = overriding_level("(panic: (pops_per_level: 0))");
+        assert!(GameConfig::default()
+            .with_overrides(GameMode::Panic, Some(&level))
+            .is_err());
+
+        let level = overriding_level("(lives: \"three\")");
+        assert!(GameConfig::default()
+            .with_overrides(GameMode::Campaign, Some(&level))
+            .is_err());
+    }
+}
@@ -0,0 +1,394 @@
+use crate::camera::MainCamera;
+use crate::game::bubble::{spawn_bubble, BubbleAssets};
+use crate::game::components::{BubbleKind, ConsoleScreen, ConsoleText, PowerUpKind};
+use crate::game::config::GameConfig;
+use crate::game::debug::cursor_position;
+use crate::game::level::LevelHandles;
+use crate::game::powerup::ActivePowerUps;
+use crate::game::{
+    BubbleState, Cheats, PlayerState, Score, Weapon, CONSOLE_BACKGROUND_COLOR, CONSOLE_LINES,
+    CONSOLE_TEXT_COLOR, CONSOLE_TEXT_SIZE, HUD_PADDING,
+};
+use crate::{AppState, Fonts, GameMode};
+use bevy::ecs::system::SystemState;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
+use std::mem;
+use std::str::FromStr;
+
+// The backtick opens a console to cheat with while testing, only in debug builds.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        app.init_resource::<Console>()
+            // Before everything else, so the keys typed into the console don't play the game.
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                console_input_system.after(InputSystem),
+            )
+            // Once the lines typed this frame are in.
+            .add_system_to_stage(CoreStage::PreUpdate, console_run_system.at_end())
+            .add_system(console_text_system)
+            .add_console_command("help", "", help_command)
+            .add_console_command("spawn", "<size> [normal|fast|bouncy]", spawn_command)
+            .add_console_command("give", "<harpoon|clock|hourglass>", give_command)
+            .add_console_command("lives", "<lives>", lives_command)
+            .add_console_command("score", "<score>", score_command)
+            .add_console_command("skip", "", skip_command)
+            .add_console_command("restart", "", restart_command)
+            .add_console_command("god", "", god_command)
+            .add_console_command("freeze", "", freeze_command);
+    }
+}
+
+// Runs a command with its arguments, returning what to print.
+pub type ConsoleHandler = fn(&mut World, &[&str]) -> Result<String, String>;
+
+struct ConsoleCommand {
+    // Arguments the command takes, shown by help and on errors.
+    usage: &'static str,
+    run: ConsoleHandler,
+}
+
+// Commands the console knows, any plugin can add its own with `add_console_command`.
+#[derive(Resource, Default)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<&'static str, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    pub fn add(&mut self, name: &'static str, usage: &'static str, run: ConsoleHandler) {
+        self.commands.insert(name, ConsoleCommand { usage, run });
+    }
+
+    // Splits a line into the command named by its first word and the rest of the words.
+    fn parse<'a>(&self, line: &'a str) -> Result<(ConsoleHandler, Vec<&'a str>), String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or("")?;
+        let command = self
+            .commands
+            .get(name)
+            .ok_or_else(|| format!("Unknown command {}, try help", name))?;
+        Ok((command.run, words.collect()))
+    }
+
+    fn usage(&self, name: &str) -> String {
+        match self.commands.get(name) {
+            Some(command) if !command.usage.is_empty() => format!("{} {}", name, command.usage),
+            _ => name.to_string(),
+        }
+    }
+}
+
+pub trait ConsoleApp {
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: ConsoleHandler,
+    ) -> &mut Self;
+}
+
+impl ConsoleApp for App {
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        run: ConsoleHandler,
+    ) -> &mut Self {
+        self.init_resource::<ConsoleCommands>();
+        self.world
+            .resource_mut::<ConsoleCommands>()
+            .add(name, usage, run);
+        self
+    }
+}
+
+#[derive(Resource, Default)]
+struct Console {
+    open: bool,
+    input: String,
+    // Lines entered but not run yet.
+    pending: Vec<String>,
+    output: VecDeque<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: String) {
+        self.output.push_back(line);
+        while self.output.len() > CONSOLE_LINES {
+            self.output.pop_front();
+        }
+    }
+}
+
+fn console_input_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    mut console: ResMut<Console>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut character_events: EventReader<ReceivedCharacter>,
+    screen_query: Query<Entity, With<ConsoleScreen>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        if console.open {
+            spawn_console(&mut commands, &fonts);
+        } else {
+            for entity in &screen_query {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+    if !console.open {
+        character_events.clear();
+        return;
+    }
+
+    for event in character_events.iter() {
+        if event.char != '`' && !event.char.is_control() {
+            console.input.push(event.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = mem::take(&mut console.input);
+        console.pending.push(line);
+    }
+    keyboard_input.reset_all();
+}
+
+fn spawn_console(commands: &mut Commands, fonts: &Fonts) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(0.),
+                        top: Val::Px(0.),
+                        ..default()
+                    },
+                    size: Size::new(Val::Percent(100.), Val::Auto),
+                    padding: UiRect::all(Val::Px(HUD_PADDING)),
+                    ..default()
+                },
+                background_color: CONSOLE_BACKGROUND_COLOR.into(),
+                ..default()
+            },
+            ConsoleScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    String::new(),
+                    TextStyle {
+                        font: fonts.default.clone(),
+                        font_size: CONSOLE_TEXT_SIZE,
+                        color: CONSOLE_TEXT_COLOR,
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+fn console_run_system(world: &mut World) {
+    let lines = mem::take(&mut world.resource_mut::<Console>().pending);
+    for line in lines {
+        let result = world
+            .resource::<ConsoleCommands>()
+            .parse(&line)
+            .and_then(|(run, args)| run(world, &args));
+        let mut console = world.resource_mut::<Console>();
+        console.print(format!("> {}", line));
+        let output = result.unwrap_or_else(|error| error);
+        if !output.is_empty() {
+            console.print(output);
+        }
+    }
+}
+
+fn console_text_system(console: Res<Console>, mut text_query: Query<&mut Text, With<ConsoleText>>) {
+    for mut text in &mut text_query {
+        let mut value = String::new();
+        for line in &console.output {
+            value.push_str(line);
+            value.push('\n');
+        }
+        value.push_str(&format!("> {}_", console.input));
+        text.sections[0].value = value;
+    }
+}
+
+fn in_game(world: &World) -> Result<(), String> {
+    if *world.resource::<State<AppState>>().current() == AppState::Game {
+        Ok(())
+    } else {
+        Err("Only in a game".to_string())
+    }
+}
+
+fn arg<T: FromStr>(world: &World, args: &[&str], index: usize, name: &str) -> Result<T, String> {
+    args.get(index)
+        .and_then(|arg| arg.parse().ok())
+        .ok_or_else(|| format!("Usage: {}", world.resource::<ConsoleCommands>().usage(name)))
+}
+
+// Marks the round as cheated.
+fn cheat(world: &mut World) {
+    world.resource_mut::<Cheats>().used = true;
+}
+
+fn help_command(world: &mut World, _: &[&str]) -> Result<String, String> {
+    let commands = world.resource::<ConsoleCommands>();
+    Ok(commands
+        .commands
+        .keys()
+        .map(|name| commands.usage(name))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+// Spawns a bubble where the cursor is, it counts towards clearing the level.
+// This is intended.
+#[allow(clippy::type_complexity)]
+fn spawn_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    in_game(world)?;
+    let size: f32 = arg(world, args, 0, "spawn")?;
+    let kind = match args.get(1).copied() {
+        None | Some("normal") => BubbleKind::Normal,
+        Some("fast") => BubbleKind::Fast,
+        Some("bouncy") => BubbleKind::Bouncy,
+        Some(kind) => return Err(format!("Unknown bubble kind {}", kind)),
+    };
+    if size < 1. {
+        return Err("Bubbles are at least of size 1".to_string());
+    }
+
+    let mut state: SystemState<(
+        Commands,
+        BubbleAssets,
+        ResMut<BubbleState>,
+        Res<GameConfig>,
+        Res<Windows>,
+        Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    )> = SystemState::new(world);
+    let (mut commands, mut bubble_assets, mut bubble_state, config, windows, camera_query) =
+        state.get_mut(world);
+    let position =
+        cursor_position(&windows, &camera_query).ok_or("The cursor is outside the window")?;
+    spawn_bubble(
+        &mut commands,
+        &mut bubble_assets,
+        &config.bubble,
+        position,
+        size,
+        kind,
+        Vec2::new(config.bubble.speed(kind), 0.),
+    );
+    bubble_state.count += 1;
+    state.apply(world);
+    cheat(world);
+    Ok(String::new())
+}
+
+fn give_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    in_game(world)?;
+    let power_up = match args.first().copied() {
+        Some("harpoon") => {
+            world.insert_resource(Weapon::Harpoon);
+            cheat(world);
+            return Ok(String::new());
+        }
+        Some("clock") => PowerUpKind::Clock,
+        Some("hourglass") => PowerUpKind::Hourglass,
+        _ => {
+            return Err(format!(
+                "Usage: {}",
+                world.resource::<ConsoleCommands>().usage("give")
+            ))
+        }
+    };
+    let config = world.resource::<GameConfig>().power_up.clone();
+    world
+        .resource_mut::<ActivePowerUps>()
+        .activate(power_up, &config);
+    cheat(world);
+    Ok(String::new())
+}
+
+fn lives_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    in_game(world)?;
+    let lives: usize = arg(world, args, 0, "lives")?;
+    // The last life is lost by a hit, never set away.
+    if lives == 0 {
+        return Err("At least one life is needed".to_string());
+    }
+    world.resource_mut::<PlayerState>().lives = lives;
+    cheat(world);
+    Ok(String::new())
+}
+
+fn score_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    in_game(world)?;
+    world.resource_mut::<Score>().score = arg(world, args, 0, "score")?;
+    cheat(world);
+    Ok(String::new())
+}
+
+// Starts the next campaign level without clearing this one, so it isn't recorded either.
+fn skip_command(world: &mut World, _: &[&str]) -> Result<String, String> {
+    in_game(world)?;
+    let game_mode = *world.resource::<GameMode>();
+    let level_handles = world.resource::<LevelHandles>();
+    if !game_mode.ends_when_cleared() || level_handles.testing() {
+        return Err(format!("{} has no next level", game_mode.name()));
+    }
+    if level_handles.selected + 1 >= level_handles.levels.len() {
+        return Err("This is the last level".to_string());
+    }
+    restart_command(world, &[])?;
+    cheat(world);
+    world.resource_mut::<LevelHandles>().selected += 1;
+    Ok(String::new())
+}
+
+fn restart_command(world: &mut World, _: &[&str]) -> Result<String, String> {
+    in_game(world)?;
+    world
+        .resource_mut::<State<AppState>>()
+        .restart()
+        .map(|_| String::new())
+        .map_err(|_| "The game is already changing state".to_string())
+}
+
+fn god_command(world: &mut World, _: &[&str]) -> Result<String, String> {
+    let mut cheats = world.resource_mut::<Cheats>();
+    cheats.god_mode = !cheats.god_mode;
+    cheats.used = true;
+    Ok(format!("God mode {}", on_off(cheats.god_mode)))
+}
+
+fn freeze_command(world: &mut World, _: &[&str]) -> Result<String, String> {
+    let mut cheats = world.resource_mut::<Cheats>();
+    cheats.frozen = !cheats.frozen;
+    cheats.used = true;
+    Ok(format!("Bubbles frozen {}", on_off(cheats.frozen)))
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
@@ -0,0 +1,426 @@
+use crate::camera::MainCamera;
+use crate::game::components::{
+    Bubble, BubbleKind, BubbleSize, DebugShape, DebugText, GameScreen, Hook, Movement, Particle,
+    Player, PowerUp, Reward, SimulationText, Wall,
+};
+use crate::game::replay::ReplayPlayback;
+use crate::game::{
+    Cheats, GameClock, Simulation, DEBUG_BUBBLE_COLOR, DEBUG_HOOK_COLOR, DEBUG_INSPECTED_COLOR,
+    DEBUG_LINE_WIDTH, DEBUG_PLAYER_COLOR, DEBUG_POWER_UP_COLOR, DEBUG_REWARD_COLOR,
+    DEBUG_SPEED_KEYS, DEBUG_TEXT_COLOR, DEBUG_TEXT_SIZE, DEBUG_TEXT_TOP, DEBUG_VELOCITY_COLOR,
+    DEBUG_VELOCITY_SCALE, DEBUG_WALL_COLOR, DEBUG_Z, HOOK_SIZE, HUD_PADDING, PLAYER_SIZE,
+};
+use crate::{AppState, Fonts};
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::query::AnyOf;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use std::fmt::Write;
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .init_resource::<DebugOverlay>()
+            .add_system(debug_toggle_system)
+            .add_system_set(
+                SystemSet::on_enter(AppState::Game).with_system(simulation_text_setup_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(debug_inspect_system)
+                    .with_system(debug_text_system.after(debug_inspect_system))
+                    .with_system(simulation_keys_system)
+                    .with_system(simulation_text_system.after(simulation_keys_system)),
+            )
+            // After the ticks of this frame, so the shapes are where the colliders ended up.
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                debug_shapes_system.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+// F3 shows the boxes `collide` tests, velocities and a panel with the frame rate, entity
+// counts and the values of the entity last clicked on.
+#[derive(Resource, Default)]
+struct DebugOverlay {
+    enabled: bool,
+    inspected: Option<Entity>,
+}
+
+// A line from one point to another, drawn as a thin sprite.
+struct Line {
+    from: Vec2,
+    to: Vec2,
+    color: Color,
+}
+
+// Which of the colliding kinds an entity is.
+type ColliderKind<'a> = (
+    Option<&'a Player>,
+    Option<&'a Hook>,
+    Option<&'a Bubble>,
+    Option<&'a Reward>,
+    Option<&'a PowerUp>,
+    Option<&'a Wall>,
+);
+
+// Box `collide` is called with for the entity, centred on it.
+fn collider_size(transform: &Transform, (player, hook, ..): ColliderKind) -> Vec2 {
+    let size = if player.is_some() {
+        Vec2::new(PLAYER_SIZE.0, PLAYER_SIZE.1)
+    } else if hook.is_some() {
+        Vec2::new(HOOK_SIZE.0, HOOK_SIZE.1)
+    } else {
+        Vec2::ONE
+    };
+    transform.scale.truncate() * size
+}
+
+fn collider_color((player, hook, bubble, reward, power_up, _): ColliderKind) -> Color {
+    if player.is_some() {
+        DEBUG_PLAYER_COLOR
+    } else if hook.is_some() {
+        DEBUG_HOOK_COLOR
+    } else if bubble.is_some() {
+        DEBUG_BUBBLE_COLOR
+    } else if reward.is_some() {
+        DEBUG_REWARD_COLOR
+    } else if power_up.is_some() {
+        DEBUG_POWER_UP_COLOR
+    } else {
+        DEBUG_WALL_COLOR
+    }
+}
+
+fn push_box(lines: &mut Vec<Line>, center: Vec2, size: Vec2, color: Color) {
+    let (min, max) = (center - size / 2., center + size / 2.);
+    let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+    for (index, &from) in corners.iter().enumerate() {
+        lines.push(Line {
+            from,
+            to: corners[(index + 1) % corners.len()],
+            color,
+        });
+    }
+}
+
+// Cursor position in the world, none outside the window.
+pub fn cursor_position(
+    windows: &Windows,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let cursor = windows.get_primary()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    Some(
+        camera
+            .viewport_to_world(camera_transform, cursor)?
+            .origin
+            .truncate(),
+    )
+}
+
+fn debug_toggle_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    fonts: Res<Fonts>,
+    mut debug_overlay: ResMut<DebugOverlay>,
+    text_query: Query<Entity, With<DebugText>>,
+    shape_query: Query<Entity, With<DebugShape>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    debug_overlay.enabled = !debug_overlay.enabled;
+    debug_overlay.inspected = None;
+    if debug_overlay.enabled {
+        commands.spawn((
+            TextBundle::from_section(
+                String::new(),
+                TextStyle {
+                    font: fonts.default.clone(),
+                    font_size: DEBUG_TEXT_SIZE,
+                    color: DEBUG_TEXT_COLOR,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(HUD_PADDING),
+                    top: Val::Px(DEBUG_TEXT_TOP),
+                    ..default()
+                },
+                ..default()
+            }),
+            DebugText,
+        ));
+    } else {
+        for entity in text_query.iter().chain(shape_query.iter()) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// A click picks the moving entity or bubble under the cursor, or nothing.
+// This is intended.
+#[allow(clippy::type_complexity)]
+fn debug_inspect_system(
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut debug_overlay: ResMut<DebugOverlay>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    inspectable_query: Query<
+        (
+            Entity,
+            &Transform,
+            AnyOf<(&Player, &Hook, &Bubble, &Reward, &PowerUp, &Wall)>,
+        ),
+        Or<(With<Movement>, With<BubbleSize>)>,
+    >,
+) {
+    if !debug_overlay.enabled || !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    debug_overlay.inspected = cursor_position(&windows, &camera_query).and_then(|cursor| {
+        inspectable_query
+            .iter()
+            .find(|(_, transform, collider)| {
+                let offset = cursor - transform.translation.truncate();
+                offset
+                    .abs()
+                    .cmple(collider_size(transform, *collider) / 2.)
+                    .all()
+            })
+            .map(|(entity, ..)| entity)
+    });
+}
+
+// This is intended.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn debug_text_system(
+    diagnostics: Res<Diagnostics>,
+    debug_overlay: Res<DebugOverlay>,
+    entity_query: Query<Entity>,
+    bubble_query: Query<(), With<Bubble>>,
+    wall_query: Query<(), With<Wall>>,
+    particle_query: Query<(), With<Particle>>,
+    inspected_query: Query<(
+        &Transform,
+        Option<&Movement>,
+        Option<&BubbleSize>,
+        Option<&BubbleKind>,
+    )>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+) {
+    if !debug_overlay.enabled {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let mut value = format!(
+        "FPS {:.0}\nEntities {}\nBubbles {}  Walls {}  Particles {}",
+        fps,
+        entity_query.iter().count(),
+        bubble_query.iter().count(),
+        wall_query.iter().count(),
+        particle_query.iter().count(),
+    );
+    match debug_overlay.inspected {
+        Some(entity) => match inspected_query.get(entity) {
+            Ok((transform, movement, size, kind)) => {
+                let _ = write!(
+                    value,
+                    "\n\nEntity {:?}\nPosition {:.1}, {:.1}",
+                    entity, transform.translation.x, transform.translation.y
+                );
+                if let Some(movement) = movement {
+                    let _ = write!(
+                        value,
+                        "\nVelocity {:.1}, {:.1}\nSlowdown {:.1}",
+                        movement.v_x, movement.v_y, movement.a
+                    );
+                }
+                if let Some(size) = size {
+                    let _ = write!(value, "\nSize {}", size.size);
+                }
+                if let Some(kind) = kind {
+                    let _ = write!(value, "\nKind {:?}", kind);
+                }
+            }
+            Err(_) => value.push_str("\n\nInspected entity is gone"),
+        },
+        None => value.push_str("\n\nClick an entity to inspect it"),
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = value.clone();
+    }
+}
+
+// This is intended.
+#[allow(clippy::type_complexity)]
+fn debug_shapes_system(
+    mut commands: Commands,
+    game_state: Res<State<AppState>>,
+    debug_overlay: Res<DebugOverlay>,
+    collider_query: Query<
+        (
+            Entity,
+            &Transform,
+            AnyOf<(&Player, &Hook, &Bubble, &Reward, &PowerUp, &Wall)>,
+        ),
+        Without<DebugShape>,
+    >,
+    movement_query: Query<(&Transform, &Movement), Without<DebugShape>>,
+    mut shape_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<DebugShape>>,
+) {
+    if !debug_overlay.enabled {
+        return;
+    }
+
+    let mut lines = Vec::new();
+    if *game_state.current() == AppState::Game {
+        for (entity, transform, collider) in &collider_query {
+            let color = if debug_overlay.inspected == Some(entity) {
+                DEBUG_INSPECTED_COLOR
+            } else {
+                collider_color(collider)
+            };
+            push_box(
+                &mut lines,
+                transform.translation.truncate(),
+                collider_size(transform, collider),
+                color,
+            );
+        }
+        for (transform, movement) in &movement_query {
+            let from = transform.translation.truncate();
+            lines.push(Line {
+                from,
+                to: from + Vec2::new(movement.v_x, movement.v_y) * DEBUG_VELOCITY_SCALE,
+                color: DEBUG_VELOCITY_COLOR,
+            });
+        }
+    }
+
+    let mut lines = lines.into_iter();
+    for (mut transform, mut sprite, mut visibility) in &mut shape_query {
+        match lines.next() {
+            Some(line) => {
+                (*transform, *sprite) = line_sprite(&line);
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+    // Lines beyond the ones already spawned are drawn from the next frame on.
+    for line in lines {
+        let (transform, sprite) = line_sprite(&line);
+        commands.spawn((
+            SpriteBundle {
+                sprite,
+                transform,
+                ..default()
+            },
+            DebugShape,
+        ));
+    }
+}
+
+fn line_sprite(line: &Line) -> (Transform, Sprite) {
+    let delta = line.to - line.from;
+    let transform = Transform {
+        translation: ((line.from + line.to) / 2.).extend(DEBUG_Z),
+        rotation: Quat::from_rotation_z(delta.y.atan2(delta.x)),
+        ..default()
+    };
+    let sprite = Sprite {
+        color: line.color,
+        custom_size: Some(Vec2::new(
+            delta.length() + DEBUG_LINE_WIDTH,
+            DEBUG_LINE_WIDTH,
+        )),
+        ..default()
+    };
+    (transform, sprite)
+}
+
+fn simulation_text_setup_system(mut commands: Commands, fonts: Res<Fonts>) {
+    commands.spawn((
+        TextBundle::from_section(
+            String::new(),
+            TextStyle {
+                font: fonts.default.clone(),
+                font_size: DEBUG_TEXT_SIZE,
+                color: DEBUG_TEXT_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                right: Val::Px(HUD_PADDING),
+                bottom: Val::Px(HUD_PADDING),
+                ..default()
+            },
+            ..default()
+        }),
+        SimulationText,
+        GameScreen,
+    ));
+}
+
+// F5 pauses the simulation, F6 runs a single tick of it and `DEBUG_SPEED_KEYS` slow it down or
+// speed it up. A watched replay has controls of its own.
+fn simulation_keys_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut simulation: ResMut<Simulation>,
+    mut cheats: ResMut<Cheats>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+) {
+    if replay_playback.is_some_and(|playback| playback.is_watched()) {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        simulation.paused = !simulation.paused;
+    }
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        simulation.paused = true;
+        simulation.step = true;
+    }
+    for (key, speed) in DEBUG_SPEED_KEYS {
+        if keyboard_input.just_pressed(key) {
+            simulation.speed = if simulation.speed == speed { 1. } else { speed };
+        }
+    }
+    // A round played at another speed or a tick at a time isn't a fair one, it counts as cheated.
+    if simulation.paused || simulation.step || simulation.speed != 1. {
+        cheats.used = true;
+    }
+}
+
+// Shown only while the simulation doesn't run normally, with the tick it is at.
+fn simulation_text_system(
+    simulation: Res<Simulation>,
+    clock: Res<GameClock>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut text_query: Query<&mut Text, With<SimulationText>>,
+) {
+    let watched = replay_playback.is_some_and(|playback| playback.is_watched());
+    let value = if watched || (!simulation.paused && simulation.speed == 1.) {
+        String::new()
+    } else if simulation.paused {
+        format!("Paused  tick {}\nF5 resume   F6 step", clock.ticks)
+    } else {
+        format!("x{}  tick {}", simulation.speed, clock.ticks)
+    };
+    for mut text in &mut text_query {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,542 @@
+use crate::camera::MainCamera;
+use crate::game::bubble::BubbleAssets;
+use crate::game::components::BubbleKind;
+use crate::game::level::{
+    BubbleDirection, BubbleSpawn, Level, LevelHandles, Obstacle, ObstacleKind,
+};
+use crate::game::theme::Palette;
+use crate::game::{
+    ASSETS_DIR, BALL_RADIUS, BOTTOM, CUSTOM_LEVEL_FILE, EDITOR_BUBBLE_SIZE_MAX,
+    EDITOR_BUBBLE_SIZE_MIN, EDITOR_CONVEYOR_SPEED, EDITOR_CURSOR_COLOR, EDITOR_GRID_COLOR,
+    EDITOR_GRID_SIZE, EDITOR_TEXT_SIZE, GLASS_Z, HUD_PADDING, LADDER_Z, LEFT, LEVEL_FILES, RIGHT,
+    TOP, WALL_SIZE,
+};
+use crate::{despawn_screen, AppState, Fonts, GameMode};
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+use std::fs;
+use std::path::Path;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Editor::open(0))
+            .add_system_set(SystemSet::on_enter(AppState::Editor).with_system(editor_setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Editor)
+                    .with_system(editor_keys_system)
+                    .with_system(editor_mouse_system)
+                    .with_system(editor_play_system)
+                    .with_system(
+                        editor_items_system
+                            .after(editor_keys_system)
+                            .after(editor_mouse_system),
+                    )
+                    .with_system(editor_cursor_system.after(editor_mouse_system))
+                    .with_system(editor_text_system.after(editor_keys_system)),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(editor_test_system))
+            .add_system_set(
+                SystemSet::on_exit(AppState::Editor).with_system(despawn_screen::<EditorScreen>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct EditorScreen;
+
+// Something placed in the level, drawn again whenever the level changes.
+#[derive(Component)]
+struct EditorItem;
+
+#[derive(Component)]
+struct EditorCursor;
+
+#[derive(Component)]
+struct EditorText;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Obstacle(ObstacleKind),
+    Bubble,
+}
+
+impl Tool {
+    fn name(&self) -> &'static str {
+        match self {
+            Tool::Obstacle(ObstacleKind::Platform) => "Wall",
+            Tool::Obstacle(ObstacleKind::Breakable) => "Breakable",
+            Tool::Obstacle(ObstacleKind::Glass) => "Glass",
+            Tool::Obstacle(ObstacleKind::Ladder) => "Ladder",
+            Tool::Obstacle(ObstacleKind::Conveyor) => "Conveyor",
+            Tool::Bubble => "Bubble",
+        }
+    }
+}
+
+// The level being edited and what the next click places.
+#[derive(Resource)]
+struct Editor {
+    level: Level,
+    // Index of the file in `files()` the level is saved to.
+    file: usize,
+    // Whether there are changes that aren't saved.
+    modified: bool,
+    // Key that throws the changes away, pressed once and waiting to be pressed again.
+    discard: Option<KeyCode>,
+    tool: Tool,
+    bubble_size: f32,
+    bubble_kind: BubbleKind,
+    direction: BubbleDirection,
+    // Corner an obstacle is dragged out from.
+    drag: Option<Vec2>,
+}
+
+// The editor's own level first, then the campaign.
+fn files() -> Vec<&'static str> {
+    std::iter::once(CUSTOM_LEVEL_FILE)
+        .chain(LEVEL_FILES)
+        .collect()
+}
+
+impl Editor {
+    // A file that doesn't exist yet opens as an empty level.
+    fn open(file: usize) -> Self {
+        let path = Path::new(ASSETS_DIR).join(files()[file]);
+        let level = match fs::read_to_string(&path) {
+            Ok(level) => match ron::from_str::<Level>(&level) {
+                Ok(level) => level,
+                Err(error) => {
+                    warn!("Ignoring invalid {}: {}", path.display(), error);
+                    Level::default()
+                }
+            },
+            Err(_) => Level::default(),
+        };
+        Self {
+            level,
+            file,
+            modified: false,
+            discard: None,
+            tool: Tool::Obstacle(ObstacleKind::Platform),
+            bubble_size: EDITOR_BUBBLE_SIZE_MIN + 1.,
+            bubble_kind: BubbleKind::Normal,
+            direction: BubbleDirection::Right,
+            drag: None,
+        }
+    }
+
+    fn save(&mut self) {
+        let path = Path::new(ASSETS_DIR).join(files()[self.file]);
+        let level = match ron::ser::to_string_pretty(&self.level, ron::ser::PrettyConfig::default())
+        {
+            Ok(level) => level,
+            Err(error) => {
+                warn!("Could not serialize the level: {}", error);
+                return;
+            }
+        };
+        if let Err(error) = fs::write(&path, level) {
+            warn!("Could not save {}: {}", path.display(), error);
+            return;
+        }
+        self.modified = false;
+    }
+
+    fn place(&mut self, from: Vec2, to: Vec2) {
+        match self.tool {
+            Tool::Bubble => self.level.bubbles.push(BubbleSpawn {
+                position: (to.x, to.y),
+                size: self.bubble_size,
+                direction: self.direction,
+                kind: self.bubble_kind,
+            }),
+            Tool::Obstacle(kind) => {
+                let center = (from + to) / 2.;
+                let size = (to - from).abs().max(Vec2::splat(EDITOR_GRID_SIZE));
+                self.level.obstacles.push(Obstacle {
+                    kind,
+                    position: (center.x, center.y),
+                    size: (size.x, size.y),
+                    waypoints: Vec::new(),
+                    speed: if kind == ObstacleKind::Conveyor {
+                        self.direction.sign() * EDITOR_CONVEYOR_SPEED
+                    } else {
+                        0.
+                    },
+                });
+            }
+        }
+        self.modified = true;
+    }
+
+    // Removes the last placed thing under the point, bubbles before obstacles.
+    fn remove(&mut self, point: Vec2) {
+        if let Some(index) = self.level.bubbles.iter().rposition(|bubble| {
+            Vec2::new(bubble.position.0, bubble.position.1).distance(point)
+                <= BALL_RADIUS * bubble.size / 2.
+        }) {
+            self.level.bubbles.remove(index);
+            self.modified = true;
+        } else if let Some(index) = self.level.obstacles.iter().rposition(|obstacle| {
+            (point.x - obstacle.position.0).abs() <= obstacle.size.0 / 2.
+                && (point.y - obstacle.position.1).abs() <= obstacle.size.1 / 2.
+        }) {
+            self.level.obstacles.remove(index);
+            self.modified = true;
+        }
+    }
+}
+
+// Cursor position in the world snapped to the grid, none outside the arena.
+fn cursor_position(
+    windows: &Windows,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let cursor = windows.get_primary()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    let position = camera
+        .viewport_to_world(camera_transform, cursor)?
+        .origin
+        .truncate();
+    let inner = Vec2::new(RIGHT - WALL_SIZE / 2., TOP - WALL_SIZE / 2.);
+    (position.abs().cmple(inner).all())
+        .then(|| (position / EDITOR_GRID_SIZE).round() * EDITOR_GRID_SIZE)
+}
+
+fn editor_setup_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    palette: Res<Palette>,
+    mut clear_color: ResMut<ClearColor>,
+    mut editor: ResMut<Editor>,
+) {
+    clear_color.0 = palette.background;
+    // Its items went with the screen when the editor was left, draw them again.
+    editor.set_changed();
+    editor.drag = None;
+
+    let line = |commands: &mut Commands, translation: Vec2, size: Vec2, color: Color| {
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform {
+                    translation: translation.extend(-0.1),
+                    scale: size.extend(1.),
+                    ..default()
+                },
+                sprite: Sprite { color, ..default() },
+                ..default()
+            },
+            EditorScreen,
+        ));
+    };
+    let mut x = LEFT;
+    while x <= RIGHT {
+        line(
+            &mut commands,
+            Vec2::new(x, 0.),
+            Vec2::new(1., TOP - BOTTOM),
+            EDITOR_GRID_COLOR,
+        );
+        x += EDITOR_GRID_SIZE;
+    }
+    let mut y = BOTTOM;
+    while y <= TOP {
+        line(
+            &mut commands,
+            Vec2::new(0., y),
+            Vec2::new(RIGHT - LEFT, 1.),
+            EDITOR_GRID_COLOR,
+        );
+        y += EDITOR_GRID_SIZE;
+    }
+    for (position, size) in [
+        (
+            Vec2::new(LEFT, 0.),
+            Vec2::new(WALL_SIZE, TOP - BOTTOM + WALL_SIZE),
+        ),
+        (
+            Vec2::new(RIGHT, 0.),
+            Vec2::new(WALL_SIZE, TOP - BOTTOM + WALL_SIZE),
+        ),
+        (
+            Vec2::new(0., BOTTOM),
+            Vec2::new(RIGHT - LEFT + WALL_SIZE, WALL_SIZE),
+        ),
+        (
+            Vec2::new(0., TOP),
+            Vec2::new(RIGHT - LEFT + WALL_SIZE, WALL_SIZE),
+        ),
+    ] {
+        line(&mut commands, position, size, palette.wall);
+    }
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: EDITOR_CURSOR_COLOR,
+                ..default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..default()
+        },
+        EditorCursor,
+        EditorScreen,
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            String::new(),
+            TextStyle {
+                font: fonts.default.clone(),
+                font_size: EDITOR_TEXT_SIZE,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(HUD_PADDING),
+                top: Val::Px(HUD_PADDING),
+                ..default()
+            },
+            ..default()
+        }),
+        EditorText,
+        EditorScreen,
+    ));
+}
+
+// 1-5 pick an obstacle and 6 a bubble, up and down change the bubble size, K its kind and
+// D the direction of bubbles and conveyors. N starts a new level, O opens the next file and
+// S saves. With unsaved changes N and O have to be pressed twice.
+fn editor_keys_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut editor: ResMut<Editor>,
+) {
+    for (key, tool) in [
+        (KeyCode::Key1, Tool::Obstacle(ObstacleKind::Platform)),
+        (KeyCode::Key2, Tool::Obstacle(ObstacleKind::Breakable)),
+        (KeyCode::Key3, Tool::Obstacle(ObstacleKind::Glass)),
+        (KeyCode::Key4, Tool::Obstacle(ObstacleKind::Ladder)),
+        (KeyCode::Key5, Tool::Obstacle(ObstacleKind::Conveyor)),
+        (KeyCode::Key6, Tool::Bubble),
+    ] {
+        if keyboard_input.just_pressed(key) {
+            editor.tool = tool;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        editor.bubble_size = (editor.bubble_size + 1.).min(EDITOR_BUBBLE_SIZE_MAX);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        editor.bubble_size = (editor.bubble_size - 1.).max(EDITOR_BUBBLE_SIZE_MIN);
+    }
+    if keyboard_input.just_pressed(KeyCode::K) {
+        editor.bubble_kind = match editor.bubble_kind {
+            BubbleKind::Normal => BubbleKind::Fast,
+            BubbleKind::Fast => BubbleKind::Bouncy,
+            BubbleKind::Bouncy => BubbleKind::Normal,
+        };
+    }
+    if keyboard_input.just_pressed(KeyCode::D) {
+        editor.direction = match editor.direction {
+            BubbleDirection::Left => BubbleDirection::Right,
+            BubbleDirection::Right => BubbleDirection::Left,
+        };
+    }
+    // Any other key calls off throwing the changes away.
+    if keyboard_input
+        .get_just_pressed()
+        .any(|&key| Some(key) != editor.discard)
+    {
+        editor.discard = None;
+    }
+    for key in [KeyCode::N, KeyCode::O] {
+        if !keyboard_input.just_pressed(key) {
+            continue;
+        }
+        if editor.modified && editor.discard != Some(key) {
+            editor.discard = Some(key);
+            continue;
+        }
+        if key == KeyCode::N {
+            editor.level = Level::default();
+            editor.modified = true;
+            editor.discard = None;
+        } else {
+            *editor = Editor::open((editor.file + 1) % files().len());
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::S) {
+        editor.save();
+        // The campaign plays the saved level from now on.
+        asset_server.reload_asset(files()[editor.file]);
+    }
+}
+
+// The left button places a bubble, or drags out an obstacle, the right one removes.
+fn editor_mouse_system(
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut editor: ResMut<Editor>,
+) {
+    let cursor = match cursor_position(&windows, &camera_query) {
+        Some(cursor) => cursor,
+        None => {
+            if mouse_input.just_released(MouseButton::Left) && editor.drag.is_some() {
+                editor.drag = None;
+            }
+            return;
+        }
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        editor.drag = Some(cursor);
+    }
+    if mouse_input.just_released(MouseButton::Left) {
+        if let Some(from) = editor.drag.take() {
+            editor.place(from, cursor);
+        }
+    }
+    if mouse_input.just_pressed(MouseButton::Right) {
+        editor.remove(cursor);
+    }
+}
+
+// T test-plays the level as it is, escape goes back to the menu.
+fn editor_play_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    editor: Res<Editor>,
+    mut levels: ResMut<Assets<Level>>,
+    mut level_handles: ResMut<LevelHandles>,
+    mut game_mode: ResMut<GameMode>,
+    mut game_state: ResMut<State<AppState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::T) {
+        level_handles.test = Some(levels.add(editor.level.clone()));
+        *game_mode = GameMode::Campaign;
+        game_state.set(AppState::Game).unwrap();
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        level_handles.test = None;
+        game_state.set(AppState::Menu).unwrap();
+    }
+}
+
+// Escape ends a test-play early.
+fn editor_test_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    level_handles: Res<LevelHandles>,
+    mut game_state: ResMut<State<AppState>>,
+) {
+    if level_handles.testing() && keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(AppState::Editor).unwrap();
+    }
+}
+
+fn editor_items_system(
+    mut commands: Commands,
+    editor: Res<Editor>,
+    palette: Res<Palette>,
+    mut bubble_assets: BubbleAssets,
+    item_query: Query<Entity, With<EditorItem>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+
+    for entity in &item_query {
+        commands.entity(entity).despawn();
+    }
+    for obstacle in editor.level.obstacles.iter() {
+        let (color, z) = match obstacle.kind {
+            ObstacleKind::Platform => (palette.wall, 0.),
+            ObstacleKind::Breakable => (palette.breakable, 0.),
+            ObstacleKind::Glass => (palette.glass, GLASS_Z),
+            ObstacleKind::Ladder => (palette.ladder, LADDER_Z),
+            ObstacleKind::Conveyor => (palette.conveyor, 0.),
+        };
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(obstacle.position.0, obstacle.position.1, z),
+                    scale: Vec3::new(obstacle.size.0, obstacle.size.1, 1.),
+                    ..default()
+                },
+                sprite: Sprite { color, ..default() },
+                ..default()
+            },
+            EditorItem,
+            EditorScreen,
+        ));
+    }
+    for bubble in editor.level.bubbles.iter() {
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: bubble_assets.circle().into(),
+                material: bubble_assets.material(bubble.kind, bubble.size),
+                transform: Transform {
+                    translation: Vec3::new(bubble.position.0, bubble.position.1, 0.1),
+                    scale: Vec3::new(BALL_RADIUS * bubble.size, BALL_RADIUS * bubble.size, 1.),
+                    ..default()
+                },
+                ..default()
+            },
+            EditorItem,
+            EditorScreen,
+        ));
+    }
+}
+
+// Shows the cell under the cursor, or the obstacle being dragged out.
+fn editor_cursor_system(
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    editor: Res<Editor>,
+    mut cursor_query: Query<(&mut Transform, &mut Visibility), With<EditorCursor>>,
+) {
+    if let Ok((mut transform, mut visibility)) = cursor_query.get_single_mut() {
+        let cursor = cursor_position(&windows, &camera_query);
+        visibility.is_visible = cursor.is_some();
+        if let Some(cursor) = cursor {
+            let from = editor.drag.unwrap_or(cursor);
+            let size = (cursor - from).abs().max(Vec2::splat(EDITOR_GRID_SIZE));
+            transform.translation = ((from + cursor) / 2.).extend(1.);
+            transform.scale = size.extend(1.);
+        }
+    }
+}
+
+fn editor_text_system(editor: Res<Editor>, mut text_query: Query<&mut Text, With<EditorText>>) {
+    if editor.is_changed() {
+        let kind = match editor.bubble_kind {
+            BubbleKind::Normal => "normal",
+            BubbleKind::Fast => "fast",
+            BubbleKind::Bouncy => "bouncy",
+        };
+        let direction = match editor.direction {
+            BubbleDirection::Left => "left",
+            BubbleDirection::Right => "right",
+        };
+        for mut text in &mut text_query {
+            text.sections[0].value = format!(
+                "{}{}{}\nTool: {}   Bubble: size {} {}   Direction: {}\n\
+                 1-5 obstacles  6 bubble  Up/Down size  K kind  D direction\n\
+                 Left place  Right remove  T test  S save  O open  N new  Esc menu",
+                files()[editor.file],
+                if editor.modified { " *" } else { "" },
+                match editor.discard {
+                    Some(KeyCode::N) => "   Unsaved changes, N again for a new level",
+                    Some(_) => "   Unsaved changes, O again to open the next file",
+                    None => "",
+                },
+                editor.tool.name(),
+                editor.bubble_size,
+                kind,
+                direction,
+            );
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use bevy::ecs::event::Event;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use super::components::BubbleKind;
+use super::SimulationStage;
+use crate::AppState;
+
+pub struct EventsPlugin;
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        add_tick_event::<CollisionEvent>(app);
+        add_tick_event::<HookFiredEvent>(app);
+        add_tick_event::<BubblePoppedEvent>(app);
+        add_tick_event::<PlayerHitEvent>(app);
+        add_tick_event::<RewardCollectedEvent>(app);
+        add_tick_event::<HookMissedEvent>(app);
+        add_tick_event::<LevelClearedEvent>(app);
+        add_tick_event::<GameOverEvent>(app);
+    }
+}
+
+// Game events are updated on the first tick of a frame instead of every frame, so they last
+// until a tick of the next frame has run. However many ticks a frame runs, or none while the
+// game stands still, no tick and no frame misses them. They are updated before anything else
+// in a tick, whatever is left over from the last round is dropped when a new one starts.
+fn add_tick_event<T: Event>(app: &mut App) {
+    app.init_resource::<Events<T>>()
+        .add_system_to_stage(SimulationStage, tick_event_update_system::<T>.at_start())
+        .add_system_set(SystemSet::on_enter(AppState::Game).with_system(clear_events_system::<T>));
+}
+
+// The time of the last update tells the frames apart.
+fn tick_event_update_system<T: Event>(
+    world: &mut World,
+    mut updated_frame: Local<Option<Instant>>,
+) {
+    let frame = world.resource::<Time>().last_update();
+    if *updated_frame != frame {
+        *updated_frame = frame;
+        world.resource_mut::<Events<T>>().update();
+    }
+}
+
+fn clear_events_system<T: Event>(mut events: ResMut<Events<T>>) {
+    events.clear();
+}
+
+// What took part in a collision, so other plugins can react to it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Collider {
+    Bubble { size: f32 },
+    Hook,
+    Wall,
+    Ceiling,
+    Breakable,
+    Player,
+    Reward,
+    PowerUp,
+}
+
+pub struct CollisionEvent {
+    pub first: Collider,
+    pub second: Collider,
+}
+
+pub struct HookFiredEvent;
+
+pub struct BubblePoppedEvent {
+    pub size: f32,
+    pub kind: BubbleKind,
+    pub position: Vec2,
+    // Horizontal speed the split bubbles fly apart with.
+    pub speed: f32,
+}
+
+pub struct PlayerHitEvent {
+    pub position: Vec2,
+}
+
+pub struct RewardCollectedEvent {
+    pub score: usize,
+    pub position: Vec2,
+}
+
+// The hook stopped at a wall without popping anything.
+pub struct HookMissedEvent {
+    pub position: Vec2,
+    // It went all the way up, rather than stopping under a platform or at a side wall.
+    pub ceiling: bool,
+}
+
+pub struct LevelClearedEvent;
+
+pub struct GameOverEvent;
@@ -0,0 +1,173 @@
+use crate::game::animation::PlayerAtlas;
+use crate::game::components::{AnimationState, Animator, GameScreen, GhostPlayer, GhostText};
+use crate::game::events::BubblePoppedEvent;
+use crate::game::replay::Replay;
+use crate::game::{
+    clock_system, GameClock, SimulationStage, TickLabel, GHOST_AHEAD_COLOR, GHOST_ALPHA,
+    GHOST_BEHIND_COLOR, GHOST_TEXT_SIZE, GHOST_Z, HUD_PADDING, PLAYER_SCALE, TIME_STEP,
+};
+use crate::{AppState, Fonts};
+use bevy::prelude::*;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Game)
+                .with_system(ghost_spawn_system)
+                .with_system(ghost_text_system),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::GameEnd
+                .set()
+                .with_system(ghost_system.after(clock_system)),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Game).with_system(ghost_reset_system));
+    }
+}
+
+// A previous run played alongside this one, compared by how soon each bubble was popped.
+#[derive(Resource)]
+pub struct Ghost {
+    replay: Replay,
+    // Bubbles popped so far in this run.
+    popped: usize,
+    // Seconds this run is behind the ghost at its last pop, negative when ahead.
+    split: Option<f32>,
+}
+
+impl Ghost {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            popped: 0,
+            split: None,
+        }
+    }
+}
+
+fn ghost_spawn_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    player_atlas: Option<Res<PlayerAtlas>>,
+    ghost: Option<Res<Ghost>>,
+    ghost_query: Query<(), With<GhostPlayer>>,
+) {
+    let ghost = match ghost {
+        Some(ghost) => ghost,
+        None => return,
+    };
+
+    if ghost.is_added() {
+        commands.spawn((
+            TextBundle::from_section(
+                String::new(),
+                TextStyle {
+                    font: fonts.default.clone(),
+                    font_size: GHOST_TEXT_SIZE,
+                    color: GHOST_AHEAD_COLOR,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(HUD_PADDING),
+                    bottom: Val::Px(HUD_PADDING),
+                    ..default()
+                },
+                ..default()
+            }),
+            GhostText,
+            GameScreen,
+        ));
+    }
+
+    if let (true, Some(player_atlas)) = (ghost_query.is_empty(), player_atlas) {
+        let (x, y) = ghost.replay.track.first().copied().unwrap_or_default();
+        commands.spawn((
+            SpriteSheetBundle {
+                texture_atlas: player_atlas.atlas.clone(),
+                sprite: TextureAtlasSprite {
+                    color: Color::rgba(1., 1., 1., GHOST_ALPHA),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: Vec3::new(x as f32, y as f32, GHOST_Z),
+                    scale: Vec3::new(PLAYER_SCALE, PLAYER_SCALE, 1.),
+                    ..default()
+                },
+                ..default()
+            },
+            Animator::default(),
+            GhostPlayer,
+            GameScreen,
+        ));
+    }
+}
+
+// Moves the ghost to where its run was at this tick and takes the split of every pop.
+pub fn ghost_system(
+    clock: Res<GameClock>,
+    ghost: Option<ResMut<Ghost>>,
+    mut ghost_query: Query<(&mut Transform, &mut Animator), With<GhostPlayer>>,
+    mut bubble_popped_events: EventReader<BubblePoppedEvent>,
+) {
+    let mut ghost = match ghost {
+        Some(ghost) => ghost,
+        None => return,
+    };
+
+    for _ in bubble_popped_events.iter() {
+        ghost.popped += 1;
+        ghost.split = ghost
+            .replay
+            .pops
+            .get(ghost.popped - 1)
+            .map(|&pop| (clock.ticks as f32 - pop as f32) * TIME_STEP);
+    }
+
+    // Once its run is over the ghost stays where it ended.
+    let track = &ghost.replay.track;
+    let tick = clock.ticks.min(track.len()).saturating_sub(1);
+    let (position, previous) = match (track.get(tick), track.get(tick.saturating_sub(1))) {
+        (Some(&position), Some(&previous)) => (position, previous),
+        _ => return,
+    };
+    if let Ok((mut transform, mut animator)) = ghost_query.get_single_mut() {
+        transform.translation.x = position.0 as f32;
+        transform.translation.y = position.1 as f32;
+        animator.set(if position.0 < previous.0 {
+            AnimationState::RunLeft
+        } else if position.0 > previous.0 {
+            AnimationState::RunRight
+        } else if position.1 != previous.1 {
+            AnimationState::Climb
+        } else {
+            AnimationState::Idle
+        });
+    }
+}
+
+fn ghost_text_system(ghost: Option<Res<Ghost>>, mut text_query: Query<&mut Text, With<GhostText>>) {
+    if let Some(ghost) = ghost {
+        let (value, color) = if ghost.popped > ghost.replay.pops.len() {
+            ("Ghost beaten".to_string(), GHOST_AHEAD_COLOR)
+        } else {
+            match ghost.split {
+                Some(split) if split > 0. => (format!("Ghost {:+.2} s", split), GHOST_BEHIND_COLOR),
+                Some(split) => (format!("Ghost {:+.2} s", split), GHOST_AHEAD_COLOR),
+                None => ("Ghost".to_string(), GHOST_AHEAD_COLOR),
+            }
+        };
+        for mut text in &mut text_query {
+            text.sections[0].value = value.clone();
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+fn ghost_reset_system(mut commands: Commands) {
+    commands.remove_resource::<Ghost>();
+}
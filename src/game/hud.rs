@@ -0,0 +1,257 @@
+use crate::game::combo::Combo;
+use crate::game::components::{
+    ComboText, GameScreen, Hearts, LevelText, PowerUpKind, PowerUpSlot, PowerUpTimerText,
+    ScoreText, TimerText, WeaponText,
+};
+use crate::game::powerup::ActivePowerUps;
+use crate::game::theme::{Palette, ThemeLabel};
+use crate::game::{
+    CurrentLevel, GameClock, GameTextures, PlayerState, Score, Weapon, COMBO_TEXT_COLOR,
+    HUD_HEART_SIZE, HUD_PADDING, HUD_POWER_UP_SIZE, HUD_TEXT_SIZE, LEVEL_TEXT_COLOR,
+    SCORE_TEXT_COLOR, TIMER_TEXT_COLOR, WEAPON_TEXT_COLOR,
+};
+use crate::records::format_time;
+use crate::{AppState, Fonts, GameMode};
+use bevy::prelude::*;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Game).with_system(hud_system.after(ThemeLabel)),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Game)
+                .with_system(hearts_system)
+                .with_system(score_text_system)
+                .with_system(combo_text_system)
+                .with_system(level_text_system)
+                .with_system(timer_text_system)
+                .with_system(weapon_text_system)
+                .with_system(power_up_tray_system),
+        );
+    }
+}
+
+fn hud_system(mut commands: Commands, fonts: Res<Fonts>, palette: Res<Palette>) {
+    let text_style = |color| TextStyle {
+        font: fonts.default.clone(),
+        font_size: HUD_TEXT_SIZE,
+        color,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(0.),
+                        top: Val::Px(0.),
+                        ..default()
+                    },
+                    size: Size::new(Val::Percent(100.), Val::Auto),
+                    padding: UiRect::all(Val::Px(HUD_PADDING)),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            GameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((NodeBundle::default(), Hearts));
+            parent.spawn((
+                TextBundle::from_section(String::new(), text_style(SCORE_TEXT_COLOR)),
+                ScoreText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(String::new(), text_style(COMBO_TEXT_COLOR)),
+                ComboText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(String::new(), text_style(LEVEL_TEXT_COLOR)),
+                LevelText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(String::new(), text_style(TIMER_TEXT_COLOR)),
+                TimerText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(String::new(), text_style(WEAPON_TEXT_COLOR)),
+                WeaponText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for kind in PowerUpKind::ALL {
+                        parent
+                            .spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        display: Display::None,
+                                        align_items: AlignItems::Center,
+                                        margin: UiRect::horizontal(Val::Px(HUD_PADDING)),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                PowerUpSlot { kind },
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(NodeBundle {
+                                    style: Style {
+                                        size: Size::new(
+                                            Val::Px(HUD_POWER_UP_SIZE),
+                                            Val::Px(HUD_POWER_UP_SIZE),
+                                        ),
+                                        margin: UiRect::right(Val::Px(HUD_PADDING / 2.)),
+                                        ..default()
+                                    },
+                                    background_color: palette.power_up(kind).into(),
+                                    ..default()
+                                });
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        String::new(),
+                                        text_style(palette.power_up(kind)),
+                                    ),
+                                    PowerUpTimerText { kind },
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+// One heart per remaining life, rebuilt whenever the lives change.
+fn hearts_system(
+    mut commands: Commands,
+    player_state: Res<PlayerState>,
+    game_textures: Res<GameTextures>,
+    hearts_query: Query<Entity, With<Hearts>>,
+    added_hearts_query: Query<(), Added<Hearts>>,
+) {
+    if !player_state.is_changed() && added_hearts_query.is_empty() {
+        return;
+    }
+
+    if let Ok(hearts_entity) = hearts_query.get_single() {
+        let mut hearts = commands.entity(hearts_entity);
+        hearts.despawn_descendants();
+        hearts.with_children(|parent| {
+            for _ in 0..player_state.lives {
+                parent.spawn(ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HUD_HEART_SIZE), Val::Px(HUD_HEART_SIZE)),
+                        margin: UiRect::right(Val::Px(HUD_PADDING / 2.)),
+                        ..default()
+                    },
+                    image: game_textures.heart.clone().into(),
+                    ..default()
+                });
+            }
+        });
+    }
+}
+
+fn score_text_system(
+    current_score: Res<Score>,
+    mut score_text_query: Query<&mut Text, With<ScoreText>>,
+) {
+    if current_score.is_changed() {
+        if let Ok(mut score_text) = score_text_query.get_single_mut() {
+            score_text.sections[0].value = format!("Score: {}", current_score.score);
+        }
+    }
+}
+
+fn combo_text_system(combo: Res<Combo>, mut combo_text_query: Query<&mut Text, With<ComboText>>) {
+    if combo.is_changed() {
+        if let Ok(mut combo_text) = combo_text_query.get_single_mut() {
+            combo_text.sections[0].value = format!("Combo: x{}", combo.multiplier);
+        }
+    }
+}
+
+fn level_text_system(
+    current_level: Res<CurrentLevel>,
+    mut level_text_query: Query<&mut Text, With<LevelText>>,
+) {
+    if current_level.is_changed() {
+        if let Ok(mut level_text) = level_text_query.get_single_mut() {
+            level_text.sections[0].value = format!("Level {}", current_level.number);
+        }
+    }
+}
+
+fn timer_text_system(
+    clock: Res<GameClock>,
+    game_mode: Res<GameMode>,
+    mut timer_text_query: Query<&mut Text, With<TimerText>>,
+) {
+    if clock.is_changed() {
+        if let Ok(mut timer_text) = timer_text_query.get_single_mut() {
+            // Time attack is timed to the millisecond.
+            let value = if *game_mode == GameMode::TimeAttack {
+                format_time(clock.millis())
+            } else {
+                let seconds = clock.seconds();
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            };
+            // The clock changes every step, only touch the text when it reads differently.
+            if timer_text.sections[0].value != value {
+                timer_text.sections[0].value = value;
+            }
+        }
+    }
+}
+
+fn weapon_text_system(
+    weapon: Res<Weapon>,
+    mut weapon_text_query: Query<&mut Text, With<WeaponText>>,
+) {
+    if weapon.is_changed() {
+        if let Ok(mut weapon_text) = weapon_text_query.get_single_mut() {
+            weapon_text.sections[0].value = weapon.name().to_string();
+        }
+    }
+}
+
+// Shows every running power-up with the seconds it has left.
+fn power_up_tray_system(
+    active_power_ups: Res<ActivePowerUps>,
+    mut slot_query: Query<(&mut Style, &PowerUpSlot)>,
+    mut timer_text_query: Query<(&mut Text, &PowerUpTimerText)>,
+) {
+    if !active_power_ups.is_changed() {
+        return;
+    }
+
+    for (mut slot_style, slot) in &mut slot_query {
+        let display = if active_power_ups.is_active(slot.kind) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if slot_style.display != display {
+            slot_style.display = display;
+        }
+    }
+    for (mut timer_text, timer) in &mut timer_text_query {
+        if let Some(remaining) = active_power_ups.remaining(timer.kind) {
+            let value = format!("{}", remaining.ceil() as usize);
+            if timer_text.sections[0].value != value {
+                timer_text.sections[0].value = value;
+            }
+        }
+    }
+}
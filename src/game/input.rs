@@ -0,0 +1,78 @@
+use crate::game::{SimulationStage, TickLabel};
+use crate::AppState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Actions::default())
+            .insert_resource(ShootLatch::default())
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(shoot_latch_system))
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Input.set().with_system(actions_system),
+            );
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Action {
+    Left = 1,
+    Right = 2,
+    Up = 4,
+    Down = 8,
+    Shoot = 16,
+}
+
+// What the player does during one tick, packed into a byte so a replay stays small.
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Actions {
+    bits: u8,
+}
+
+impl Actions {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bits & action as u8 != 0
+    }
+
+    pub fn press(&mut self, action: Action) {
+        self.bits |= action as u8;
+    }
+}
+
+// A shot is pressed in a frame but used in a tick, frames without a tick must not lose it.
+#[derive(Resource, Default)]
+pub struct ShootLatch {
+    pressed: bool,
+}
+
+fn shoot_latch_system(keyboard_input: Res<Input<KeyCode>>, mut shoot_latch: ResMut<ShootLatch>) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        shoot_latch.pressed = true;
+    }
+}
+
+pub fn actions_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut shoot_latch: ResMut<ShootLatch>,
+    mut actions: ResMut<Actions>,
+) {
+    *actions = Actions::default();
+    for (key, action) in [
+        (KeyCode::Left, Action::Left),
+        (KeyCode::Right, Action::Right),
+        (KeyCode::Up, Action::Up),
+        (KeyCode::Down, Action::Down),
+    ] {
+        if keyboard_input.pressed(key) {
+            actions.press(action);
+        }
+    }
+    if shoot_latch.pressed {
+        actions.press(Action::Shoot);
+        shoot_latch.pressed = false;
+    }
+}
@@ -0,0 +1,142 @@
+use crate::camera::{MainCamera, VIEW_HEIGHT, VIEW_WIDTH};
+use crate::game::components::Bubble;
+use crate::game::events::{BubblePoppedEvent, PlayerHitEvent};
+use crate::game::{
+    BubbleState, Simulation, DEATH_TRAUMA, HIT_STOP_FRAMES, HIT_STOP_SIZE, LAST_BUBBLE_ZOOM,
+    POP_TRAUMA, SHAKE_MAX_ANGLE, SHAKE_MAX_OFFSET, SHAKE_POP_SIZE, TRAUMA_DECAY, ZOOM_SPEED,
+};
+use crate::settings::Accessibility;
+use crate::{AppState, GameMode};
+use bevy::prelude::*;
+use rand::Rng;
+
+pub struct JuicePlugin;
+
+impl Plugin for JuicePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(juice_setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(trauma_system)
+                    .with_system(camera_juice_system.after(trauma_system)),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Game).with_system(camera_reset_system));
+    }
+}
+
+#[derive(Resource)]
+struct CameraJuice {
+    trauma: f32,
+    zoom: f32,
+    // Point the view drifts towards while zoomed in.
+    focus: Vec2,
+}
+
+impl Default for CameraJuice {
+    fn default() -> Self {
+        Self {
+            trauma: 0.,
+            zoom: 1.,
+            focus: Vec2::ZERO,
+        }
+    }
+}
+
+impl CameraJuice {
+    fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.);
+    }
+}
+
+fn juice_setup_system(mut commands: Commands) {
+    commands.insert_resource(CameraJuice::default());
+}
+
+// Big pops and getting hit shake the screen, splitting a big bubble also holds the game still.
+fn trauma_system(
+    accessibility: Res<Accessibility>,
+    mut camera_juice: ResMut<CameraJuice>,
+    mut simulation: ResMut<Simulation>,
+    mut bubble_popped_events: EventReader<BubblePoppedEvent>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+) {
+    if accessibility.reduced_motion {
+        bubble_popped_events.clear();
+        player_hit_events.clear();
+        return;
+    }
+
+    for bubble_popped in bubble_popped_events.iter() {
+        if bubble_popped.size >= SHAKE_POP_SIZE {
+            camera_juice.add_trauma(POP_TRAUMA * (bubble_popped.size - SHAKE_POP_SIZE + 1.));
+        }
+        if bubble_popped.size >= HIT_STOP_SIZE {
+            simulation.hit_stop(HIT_STOP_FRAMES);
+        }
+    }
+
+    for _ in player_hit_events.iter() {
+        camera_juice.add_trauma(DEATH_TRAUMA);
+    }
+}
+
+fn camera_juice_system(
+    time: Res<Time>,
+    accessibility: Res<Accessibility>,
+    game_mode: Res<GameMode>,
+    bubble_state: Res<BubbleState>,
+    mut camera_juice: ResMut<CameraJuice>,
+    bubble_query: Query<&Transform, (With<Bubble>, Without<MainCamera>)>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    camera_juice.trauma = (camera_juice.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.);
+
+    // Only zoom in on the last bubble when popping it ends the game, and not on a level that
+    // starts with a single bubble.
+    let last_bubble = match bubble_query.get_single() {
+        Ok(bubble)
+            if !accessibility.reduced_motion
+                && game_mode.ends_when_cleared()
+                && bubble_state.popped > 0 =>
+        {
+            Some(bubble.translation.truncate())
+        }
+        _ => None,
+    };
+    let (zoom, focus) = match last_bubble {
+        Some(position) => (LAST_BUBBLE_ZOOM, position),
+        None => (1., Vec2::ZERO),
+    };
+    let t = (ZOOM_SPEED * time.delta_seconds()).min(1.);
+    camera_juice.zoom += (zoom - camera_juice.zoom) * t;
+    camera_juice.focus = camera_juice.focus.lerp(focus, t);
+
+    // The view may only move as far as it stays inside the letterbox.
+    let margin = Vec2::new(VIEW_WIDTH, VIEW_HEIGHT) * (1. - camera_juice.zoom) / 2.;
+    let offset = camera_juice.focus.clamp(-margin, margin);
+
+    let shake = camera_juice.trauma * camera_juice.trauma;
+    let mut rng = rand::thread_rng();
+    let shake_offset =
+        Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * SHAKE_MAX_OFFSET * shake;
+    let shake_angle = rng.gen_range(-1.0..1.0) * SHAKE_MAX_ANGLE * shake;
+
+    for (mut transform, mut projection) in &mut camera_query {
+        let position = offset + shake_offset;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        transform.rotation = Quat::from_rotation_z(shake_angle);
+        projection.scale = camera_juice.zoom;
+    }
+}
+
+fn camera_reset_system(
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    for (mut transform, mut projection) in &mut camera_query {
+        transform.translation.x = 0.;
+        transform.translation.y = 0.;
+        transform.rotation = Quat::IDENTITY;
+        projection.scale = 1.;
+    }
+}
@@ -0,0 +1,243 @@
+use crate::cli::Cli;
+use crate::game::components::{Bubble, BubbleKind, BubbleSize, Movement, Player};
+use crate::game::config::ConfigHandle;
+use crate::game::level::LevelHandles;
+use crate::game::replay::{Replay, ReplayPlayback};
+use crate::game::{
+    clock_system, game_end_system, round_reset_system, simulation_frame_system, CurrentLevel,
+    GameClock, PlayerState, Score, Simulation, SimulationStage, TickLabel, FAST_FORWARD_TICKS,
+};
+use crate::{AppState, GameMode};
+use bevy::app::AppExit;
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use serde::Serialize;
+use std::fs;
+
+pub struct LaunchPlugin;
+
+impl Plugin for LaunchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, launch_setup_system)
+            .add_system_to_stage(CoreStage::PreUpdate, launch_system)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                headless_system.before(simulation_frame_system),
+            )
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::GameEnd
+                    .set()
+                    .with_system(tick_limit_system.after(clock_system).after(game_end_system)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game)
+                    .with_system(dump_system.before(round_reset_system)),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(headless_exit_system));
+    }
+}
+
+// What a round ended with, written out for scripted runs to check.
+#[derive(Serialize)]
+struct StateDump {
+    mode: GameMode,
+    level: String,
+    ticks: usize,
+    millis: u64,
+    score: usize,
+    lives: usize,
+    hits: usize,
+    level_number: usize,
+    player: Option<(f32, f32)>,
+    bubbles: Vec<BubbleDump>,
+}
+
+#[derive(Serialize)]
+struct BubbleDump {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    size: f32,
+    kind: BubbleKind,
+}
+
+// Picks the mode, level or replay of a game started from the command line.
+fn launch_setup_system(
+    mut commands: Commands,
+    cli: Res<Cli>,
+    asset_server: Res<AssetServer>,
+    mut game_mode: ResMut<GameMode>,
+    mut level_handles: ResMut<LevelHandles>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if let Some(path) = &cli.replay {
+        match Replay::load(path) {
+            Some(replay) => {
+                *game_mode = replay.mode;
+                level_handles.select(&asset_server, &replay.level);
+                commands.insert_resource(ReplayPlayback::new(replay));
+            }
+            None => {
+                error!("Could not watch {}", path.display());
+                app_exit_events.send(AppExit);
+            }
+        }
+        return;
+    }
+
+    *game_mode = cli.mode.unwrap_or_default();
+    if let Some(level) = cli.level {
+        if !game_mode.ends_when_cleared() {
+            warn!("{} is played without levels", game_mode.name());
+        } else if level < level_handles.levels.len() {
+            level_handles.selected = level;
+        } else {
+            error!("There are only {} levels", level_handles.levels.len());
+            app_exit_events.send(AppExit);
+        }
+    }
+}
+
+// Leaves the splash for the menu, or for the game once its level and config are loaded. A game
+// started from the command line can't be played without them.
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn launch_system(
+    cli: Res<Cli>,
+    asset_server: Res<AssetServer>,
+    level_handles: Res<LevelHandles>,
+    config_handle: Res<ConfigHandle>,
+    game_mode: Res<GameMode>,
+    mut game_state: ResMut<State<AppState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+    mut launched: Local<bool>,
+) {
+    if *launched || *game_state.current() != AppState::Splash {
+        return;
+    }
+
+    let state = if cli.starts_game() {
+        let handles = [
+            level_handles.current(*game_mode).clone_untyped(),
+            config_handle.0.clone_untyped(),
+        ];
+        if let Some(failed) = handles
+            .iter()
+            .find(|handle| asset_server.get_load_state(*handle) == LoadState::Failed)
+        {
+            let path = asset_server
+                .get_handle_path(failed)
+                .map(|path| path.path().display().to_string())
+                .unwrap_or_default();
+            error!("Could not load {}", path);
+            app_exit_events.send(AppExit);
+            *launched = true;
+            return;
+        }
+        let loaded = handles
+            .iter()
+            .all(|handle| asset_server.get_load_state(handle) == LoadState::Loaded);
+        if !loaded {
+            return;
+        }
+        AppState::Game
+    } else if cli.skip_splash {
+        AppState::Menu
+    } else {
+        *launched = true;
+        return;
+    };
+    // The splash may not be entered yet on the first frame, that's tried again.
+    *launched = game_state.set(state).is_ok();
+}
+
+// Without a window nothing waits for the clock, the game runs as many ticks as it can.
+fn headless_system(
+    cli: Res<Cli>,
+    game_state: Res<State<AppState>>,
+    mut simulation: ResMut<Simulation>,
+) {
+    if cli.headless && *game_state.current() == AppState::Game {
+        simulation.fast_forward = FAST_FORWARD_TICKS;
+    }
+}
+
+// Ends the round after the ticks asked for, or once a replay watched headless runs out.
+fn tick_limit_system(
+    cli: Res<Cli>,
+    clock: Res<GameClock>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut game_state: ResMut<State<AppState>>,
+    mut simulation: ResMut<Simulation>,
+) {
+    let ticks_run = cli.ticks.is_some_and(|ticks| clock.ticks >= ticks);
+    let replay_over = cli.headless
+        && replay_playback.is_some_and(|playback| playback.is_watched() && playback.is_finished());
+    // The game may have ended in this tick already.
+    if (ticks_run || replay_over) && game_state.set(AppState::Menu).is_ok() {
+        simulation.stop();
+    }
+}
+
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn dump_system(
+    cli: Res<Cli>,
+    asset_server: Res<AssetServer>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+    clock: Res<GameClock>,
+    score: Res<Score>,
+    player_state: Res<PlayerState>,
+    current_level: Res<CurrentLevel>,
+    player_query: Query<&Transform, With<Player>>,
+    bubble_query: Query<(&Transform, &Movement, &BubbleSize, &BubbleKind), With<Bubble>>,
+) {
+    let path = match &cli.dump {
+        Some(path) => path,
+        None => return,
+    };
+
+    let dump = StateDump {
+        mode: *game_mode,
+        level: level_handles.path(&asset_server, *game_mode),
+        ticks: clock.ticks,
+        millis: clock.millis(),
+        score: score.score,
+        lives: player_state.lives,
+        hits: player_state.hits,
+        level_number: current_level.number,
+        player: player_query
+            .get_single()
+            .ok()
+            .map(|transform| (transform.translation.x, transform.translation.y)),
+        bubbles: bubble_query
+            .iter()
+            .map(|(transform, movement, size, kind)| BubbleDump {
+                position: (transform.translation.x, transform.translation.y),
+                velocity: (movement.v_x, movement.v_y),
+                size: size.size,
+                kind: *kind,
+            })
+            .collect(),
+    };
+    let dump = match ron::ser::to_string_pretty(&dump, ron::ser::PrettyConfig::default()) {
+        Ok(dump) => dump,
+        Err(error) => {
+            warn!("Could not serialize the game state: {}", error);
+            return;
+        }
+    };
+    if path.as_os_str() == "-" {
+        println!("{}", dump);
+    } else if let Err(error) = fs::write(path, dump) {
+        warn!("Could not write {}: {}", path.display(), error);
+    }
+}
+
+// A headless run is over with its first round.
+fn headless_exit_system(cli: Res<Cli>, mut app_exit_events: EventWriter<AppExit>) {
+    if cli.headless {
+        app_exit_events.send(AppExit);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::GameMode;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
+
+use super::components::BubbleKind;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>().init_asset_loader::<LevelLoader>();
+    }
+}
+
+#[derive(Resource)]
+pub struct LevelHandles {
+    pub levels: Vec<Handle<Level>>,
+    // Index of the level the campaign and time attack are played on.
+    pub selected: usize,
+    // Empty arena the endless modes drop their bubbles into.
+    pub endless: Handle<Level>,
+    // Level being test-played from the editor, in place of the selected one.
+    pub test: Option<Handle<Level>>,
+}
+
+impl LevelHandles {
+    pub fn current(&self, game_mode: GameMode) -> &Handle<Level> {
+        match game_mode {
+            GameMode::Campaign | GameMode::TimeAttack => {
+                self.test.as_ref().unwrap_or(&self.levels[self.selected])
+            }
+            GameMode::Survival | GameMode::Panic => &self.endless,
+        }
+    }
+
+    pub fn testing(&self) -> bool {
+        self.test.is_some()
+    }
+
+    // Asset path of the level, which names it in replays and records.
+    pub fn path(&self, asset_server: &AssetServer, game_mode: GameMode) -> String {
+        Self::handle_path(asset_server, self.current(game_mode))
+    }
+
+    pub fn level_path(&self, asset_server: &AssetServer, index: usize) -> String {
+        Self::handle_path(asset_server, &self.levels[index])
+    }
+
+    fn handle_path(asset_server: &AssetServer, handle: &Handle<Level>) -> String {
+        asset_server
+            .get_handle_path(handle)
+            .map(|path| path.path().display().to_string())
+            .unwrap_or_default()
+    }
+
+    // Selects the level with the given path, keeping the selection if there is none.
+    pub fn select(&mut self, asset_server: &AssetServer, path: &str) {
+        if let Some(index) =
+            (0..self.levels.len()).find(|&index| self.level_path(asset_server, index) == path)
+        {
+            self.selected = index;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, TypeUuid, Clone, Default)]
+#[uuid = "6b9a989d-9cf8-491d-a704-5d63588535d5"]
+pub struct Level {
+    // Path of the theme the level is drawn with, the default one if left out.
+    #[serde(default)]
+    pub theme: Option<String>,
+    pub bubbles: Vec<BubbleSpawn>,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub medals: Option<Medals>,
+    // Clear time in seconds the campaign gives its third star for.
+    #[serde(default)]
+    pub par: Option<f32>,
+    // Values of the game config changed on this level only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<ron::Value>,
+}
+
+impl Level {
+    // Bubbles split down to size 1 by whole steps, and obstacles need an area to collide with.
+    fn validate(&self) -> Result<(), String> {
+        if let Some(bubble) = self
+            .bubbles
+            .iter()
+            .find(|bubble| bubble.size < 1. || bubble.size.fract() != 0.)
+        {
+            return Err(format!(
+                "A bubble at {:?} has size {}, sizes are whole numbers from 1",
+                bubble.position, bubble.size
+            ));
+        }
+        if let Some(obstacle) = self
+            .obstacles
+            .iter()
+            .find(|obstacle| obstacle.size.0 <= 0. || obstacle.size.1 <= 0.)
+        {
+            return Err(format!(
+                "An obstacle at {:?} has size {:?}",
+                obstacle.position, obstacle.size
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Clear times in seconds a time attack run has to beat for each medal.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Medals {
+    pub gold: f32,
+    pub silver: f32,
+    pub bronze: f32,
+}
+
+impl Medals {
+    pub fn medal(&self, millis: u64) -> Option<Medal> {
+        let seconds = millis as f32 / 1000.;
+        if seconds <= self.gold {
+            Some(Medal::Gold)
+        } else if seconds <= self.silver {
+            Some(Medal::Silver)
+        } else if seconds <= self.bronze {
+            Some(Medal::Bronze)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Medal {
+    Gold,
+    Silver,
+    Bronze,
+}
+
+impl Medal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Medal::Gold => "Gold",
+            Medal::Silver => "Silver",
+            Medal::Bronze => "Bronze",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub enum BubbleDirection {
+    Left,
+    Right,
+}
+
+impl BubbleDirection {
+    pub fn sign(&self) -> f32 {
+        match self {
+            BubbleDirection::Left => -1.,
+            BubbleDirection::Right => 1.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BubbleSpawn {
+    pub position: (f32, f32),
+    pub size: f32,
+    pub direction: BubbleDirection,
+    #[serde(default)]
+    pub kind: BubbleKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleKind {
+    // Bubbles bounce off it, hooks stop at it.
+    Platform,
+    // Like a platform, but destroyed by the hook.
+    Breakable,
+    // Bubbles pass through it, hooks stop at it.
+    Glass,
+    // Not solid, the player can climb it.
+    Ladder,
+    // Solid floor segment that pushes the player standing on it by `speed`.
+    Conveyor,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    // Points the obstacle travels through in a loop, starting from `position`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waypoints: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub speed: f32,
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = ron::de::from_bytes::<Level>(bytes)?;
+            level.validate().map_err(bevy::asset::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn medals_go_to_times_up_to_their_limit() {
+        let medals = Medals {
+            gold: 20.,
+            silver: 30.,
+            bronze: 45.,
+        };
+        assert!(medals.medal(12_000) == Some(Medal::Gold));
+        assert!(medals.medal(20_000) == Some(Medal::Gold));
+        assert!(medals.medal(20_001) == Some(Medal::Silver));
+        assert!(medals.medal(45_000) == Some(Medal::Bronze));
+        assert!(medals.medal(45_001).is_none());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::game::bubble::bubble_pop_system;
+use crate::game::components::{GameScreen, PanicMeter};
+use crate::game::config::{ConfigLabel, GameConfig, PanicConfig};
+use crate::game::powerup::{power_up_timer_system, ActivePowerUps};
+use crate::game::survival::survival_spawn_system;
+use crate::game::wave::{BubbleDrops, WaveConfig, WaveSpawner};
+use crate::game::{
+    BubbleState, CurrentLevel, SimulationStage, TickLabel, PANIC_METER_BACKGROUND_COLOR,
+    PANIC_METER_COLOR, PANIC_METER_SIZE, PANIC_METER_X, PANIC_METER_Y, TIME_STEP,
+};
+use crate::{AppState, GameMode};
+use bevy::prelude::*;
+
+pub struct PanicPlugin;
+
+impl Plugin for PanicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Game)
+                .with_system(panic_setup_system.after(ConfigLabel))
+                .with_system(panic_meter_system),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Movement.set().with_system(
+                panic_spawn_system
+                    .after(power_up_timer_system)
+                    .after(survival_spawn_system),
+            ),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Reactions
+                .set()
+                .with_system(panic_level_system.after(bubble_pop_system)),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Game).with_system(panic_meter_update_system),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct PanicState {
+    // Steps up with every level.
+    waves: WaveSpawner,
+    // Pops counted towards the next level.
+    pops: usize,
+    // Value of `BubbleState::popped` already counted in `pops`.
+    last_popped: usize,
+}
+
+impl PanicState {
+    fn new(config: &PanicConfig) -> Self {
+        Self {
+            waves: WaveSpawner::new(&WaveConfig::from(config)),
+            pops: 0,
+            last_popped: 0,
+        }
+    }
+
+    fn level(&self) -> usize {
+        self.waves.step + 1
+    }
+
+    fn progress(&self, config: &PanicConfig) -> f32 {
+        self.pops as f32 / config.pops_per_level as f32
+    }
+}
+
+fn panic_setup_system(mut commands: Commands, config: Res<GameConfig>) {
+    commands.insert_resource(PanicState::new(&config.panic));
+}
+
+fn panic_meter_system(mut commands: Commands, game_mode: Res<GameMode>) {
+    if *game_mode != GameMode::Panic {
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(PANIC_METER_X),
+                        top: Val::Px(PANIC_METER_Y),
+                        ..default()
+                    },
+                    size: Size::new(Val::Px(PANIC_METER_SIZE.0), Val::Px(PANIC_METER_SIZE.1)),
+                    ..default()
+                },
+                background_color: PANIC_METER_BACKGROUND_COLOR.into(),
+                ..default()
+            },
+            GameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                        ..default()
+                    },
+                    background_color: PANIC_METER_COLOR.into(),
+                    ..default()
+                },
+                PanicMeter,
+            ));
+        });
+}
+
+// Every few pops fill the meter and advance the panic level.
+fn panic_level_system(
+    mut panic_state: ResMut<PanicState>,
+    mut current_level: ResMut<CurrentLevel>,
+    bubble_state: Res<BubbleState>,
+    game_mode: Res<GameMode>,
+    config: Res<GameConfig>,
+) {
+    if *game_mode != GameMode::Panic {
+        return;
+    }
+
+    let popped = bubble_state.popped - panic_state.last_popped;
+    if popped == 0 {
+        return;
+    }
+
+    panic_state.last_popped = bubble_state.popped;
+    panic_state.pops += popped;
+    while panic_state.pops >= config.panic.pops_per_level {
+        panic_state.pops -= config.panic.pops_per_level;
+        panic_state.waves.step += 1;
+        current_level.number = panic_state.level();
+    }
+}
+
+fn panic_spawn_system(
+    mut panic_state: ResMut<PanicState>,
+    mut drops: BubbleDrops,
+    active_power_ups: Res<ActivePowerUps>,
+    game_mode: Res<GameMode>,
+    config: Res<GameConfig>,
+) {
+    if *game_mode != GameMode::Panic {
+        return;
+    }
+
+    let wave_config = WaveConfig::from(&config.panic);
+    let waves = &mut panic_state.waves;
+    let delta = TIME_STEP * active_power_ups.wave_timer_scale();
+    if waves.tick(delta, &wave_config, &config.bubble, &mut drops) {
+        waves.restart(&wave_config);
+    }
+}
+
+fn panic_meter_update_system(
+    panic_state: Res<PanicState>,
+    config: Res<GameConfig>,
+    mut meter_query: Query<&mut Style, With<PanicMeter>>,
+) {
+    if panic_state.is_changed() {
+        if let Ok(mut meter_style) = meter_query.get_single_mut() {
+            meter_style.size.width = Val::Percent(100. * panic_state.progress(&config.panic));
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::game::components::{GameScreen, Particle};
+use crate::game::events::{
+    BubblePoppedEvent, HookMissedEvent, PlayerHitEvent, RewardCollectedEvent,
+};
+use crate::game::theme::Palette;
+use crate::game::{
+    rope_hook_system, SimulationStage, TickLabel, DEATH_PARTICLES, DEATH_PARTICLE_COLOR,
+    DEATH_PARTICLE_SPEED, DEATH_PARTICLE_TIME, PARTICLE_GRAVITY, PARTICLE_MAX, PARTICLE_SIZE,
+    PARTICLE_Z, POP_PARTICLES, POP_PARTICLE_SPEED, POP_PARTICLE_TIME, SPARKLE_PARTICLES,
+    SPARKLE_PARTICLE_SPEED, SPARKLE_PARTICLE_TIME, SPARK_COLOR, SPARK_PARTICLES,
+    SPARK_PARTICLE_SPEED, SPARK_PARTICLE_TIME, TIME_STEP,
+};
+use crate::settings::Accessibility;
+use crate::AppState;
+use bevy::prelude::*;
+use rand::Rng;
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::time::Duration;
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(AppState::Game).with_system(particle_emit_system))
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Movement
+                    .set()
+                    .with_system(particle_system.after(rope_hook_system)),
+            );
+    }
+}
+
+struct Burst {
+    position: Vec2,
+    color: Color,
+    count: usize,
+    speed: f32,
+    time: f32,
+    // Directions the particles fly in, in radians.
+    angles: Range<f32>,
+}
+
+// Particles are only for show, so they use their own rng and leave the game one alone.
+fn spawn_burst(commands: &mut Commands, budget: &mut usize, burst: Burst) {
+    let mut rng = rand::thread_rng();
+    let count = burst.count.min(*budget);
+    *budget -= count;
+
+    for _ in 0..count {
+        let angle = rng.gen_range(burst.angles.clone());
+        let speed = burst.speed * rng.gen_range(0.5..1.);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: burst.color,
+                    ..default()
+                },
+                transform: Transform {
+                    translation: burst.position.extend(PARTICLE_Z),
+                    scale: Vec3::new(PARTICLE_SIZE, PARTICLE_SIZE, 1.),
+                    ..default()
+                },
+                ..default()
+            },
+            Particle {
+                velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+                lifetime: Timer::from_seconds(burst.time, TimerMode::Once),
+            },
+            GameScreen,
+        ));
+    }
+}
+
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn particle_emit_system(
+    mut commands: Commands,
+    palette: Res<Palette>,
+    accessibility: Res<Accessibility>,
+    particle_query: Query<(), With<Particle>>,
+    mut bubble_popped_events: EventReader<BubblePoppedEvent>,
+    mut reward_collected_events: EventReader<RewardCollectedEvent>,
+    mut hook_missed_events: EventReader<HookMissedEvent>,
+    mut player_hit_events: EventReader<PlayerHitEvent>,
+) {
+    // With reduced motion no particles are spawned at all.
+    let mut budget = if accessibility.reduced_motion {
+        0
+    } else {
+        PARTICLE_MAX.saturating_sub(particle_query.iter().count())
+    };
+
+    for bubble_popped in bubble_popped_events.iter() {
+        spawn_burst(
+            &mut commands,
+            &mut budget,
+            Burst {
+                position: bubble_popped.position,
+                color: palette.bubble(bubble_popped.kind),
+                count: POP_PARTICLES * bubble_popped.size as usize,
+                speed: POP_PARTICLE_SPEED,
+                time: POP_PARTICLE_TIME,
+                angles: 0.0..2. * PI,
+            },
+        );
+    }
+    for reward_collected in reward_collected_events.iter() {
+        spawn_burst(
+            &mut commands,
+            &mut budget,
+            Burst {
+                position: reward_collected.position,
+                color: palette.reward,
+                count: SPARKLE_PARTICLES,
+                speed: SPARKLE_PARTICLE_SPEED,
+                time: SPARKLE_PARTICLE_TIME,
+                angles: 0.0..2. * PI,
+            },
+        );
+    }
+    // The hook only stops on something above it, so the sparks fly down.
+    for hook_missed in hook_missed_events.iter() {
+        spawn_burst(
+            &mut commands,
+            &mut budget,
+            Burst {
+                position: hook_missed.position,
+                color: SPARK_COLOR,
+                count: SPARK_PARTICLES,
+                speed: SPARK_PARTICLE_SPEED,
+                time: SPARK_PARTICLE_TIME,
+                angles: PI..2. * PI,
+            },
+        );
+    }
+    for player_hit in player_hit_events.iter() {
+        spawn_burst(
+            &mut commands,
+            &mut budget,
+            Burst {
+                position: player_hit.position,
+                color: DEATH_PARTICLE_COLOR,
+                count: DEATH_PARTICLES,
+                speed: DEATH_PARTICLE_SPEED,
+                time: DEATH_PARTICLE_TIME,
+                angles: 0.0..2. * PI,
+            },
+        );
+    }
+}
+
+// Moves the particles under gravity and fades them out over their lifetime.
+pub fn particle_system(
+    mut commands: Commands,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (particle_entity, mut particle, mut transform, mut sprite) in &mut particle_query {
+        if particle
+            .lifetime
+            .tick(Duration::from_secs_f32(TIME_STEP))
+            .finished()
+        {
+            commands.entity(particle_entity).despawn();
+            continue;
+        }
+
+        particle.velocity.y -= PARTICLE_GRAVITY * TIME_STEP;
+        transform.translation += (particle.velocity * TIME_STEP).extend(0.);
+        sprite.color.set_a(particle.lifetime.percent_left());
+    }
+}
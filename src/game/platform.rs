@@ -0,0 +1,30 @@
+use crate::game::components::{Movement, Waypoints};
+use crate::game::{velocity_system, SimulationStage, TickLabel, TIME_STEP};
+use bevy::prelude::*;
+
+pub struct PlatformPlugin;
+
+impl Plugin for PlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Movement
+                .set()
+                .with_system(waypoint_system.before(velocity_system)),
+        );
+    }
+}
+
+// Steers the platform towards its next waypoint, `velocity_system` does the moving.
+pub fn waypoint_system(mut query: Query<(&Transform, &mut Movement, &mut Waypoints)>) {
+    for (transform, mut movement, mut waypoints) in &mut query {
+        let position = transform.translation.truncate();
+        if position.distance(waypoints.points[waypoints.next]) <= waypoints.speed * TIME_STEP {
+            waypoints.next = (waypoints.next + 1) % waypoints.points.len();
+        }
+
+        let direction = (waypoints.points[waypoints.next] - position).normalize_or_zero();
+        movement.v_x = direction.x * waypoints.speed;
+        movement.v_y = direction.y * waypoints.speed;
+    }
+}
@@ -1,24 +1,37 @@
-use crate::game::components::{GameScreen, Hook, Movement, Player};
+use crate::game::animation::PlayerAtlas;
+use crate::game::components::{
+    Animator, Conveyor, GameScreen, Hook, Ladder, Movement, Player, PlayerBody, Wall,
+};
+use crate::game::config::GameConfig;
+use crate::game::events::HookFiredEvent;
+use crate::game::input::{Action, Actions};
+use crate::game::platform::waypoint_system;
 use crate::game::{
-    GameTextures, PlayerState, BOTTOM, HOOK_SIZE, HOOK_SPEED, HOOK_WIDTH_SCALE, LEFT, PLAYER_SCALE,
-    PLAYER_SIZE, PLAYER_SPEED, RIGHT, TIME_STEP, WALL_SIZE,
+    velocity_system, GameTextures, PlayerState, SimulationStage, TickLabel, BOTTOM, HOOK_SIZE,
+    HOOK_WIDTH_SCALE, LEFT, PLAYER_SCALE, PLAYER_SIZE, RIGHT, TIME_STEP, WALL_SIZE,
 };
-use crate::AppState;
 use bevy::prelude::*;
-use bevy::time::FixedTimestep;
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayerState::default())
-            .add_system_set(
-                SystemSet::on_in_stack_update(AppState::Game).with_system(spawn_player_system),
-            )
-            .add_system_set(
-                SystemSet::on_update(AppState::Game)
-                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
-                    .with_system(move_player_system)
-                    .with_system(shot_player_system),
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Movement
+                    .set()
+                    .with_system(spawn_player_system)
+                    .with_system(
+                        move_player_system
+                            .after(spawn_player_system)
+                            .after(waypoint_system)
+                            .before(velocity_system),
+                    )
+                    .with_system(
+                        shot_player_system
+                            .after(move_player_system)
+                            .before(velocity_system),
+                    ),
             );
     }
 }
@@ -26,13 +39,13 @@ impl Plugin for PlayerPlugin {
 fn spawn_player_system(
     mut commands: Commands,
     mut player_state: ResMut<PlayerState>,
-    game_textures: Res<GameTextures>,
+    player_atlas: Option<Res<PlayerAtlas>>,
 ) {
-    if !player_state.is_alive {
+    if let (false, Some(player_atlas)) = (player_state.is_alive, player_atlas) {
         let player_y_pos = BOTTOM + WALL_SIZE / 2. + PLAYER_SIZE.1 / 2. * PLAYER_SCALE;
         commands.spawn((
-            SpriteBundle {
-                texture: game_textures.player.clone(),
+            SpriteSheetBundle {
+                texture_atlas: player_atlas.atlas.clone(),
                 transform: Transform {
                     translation: Vec3::new(0.0, player_y_pos, 0.),
                     scale: Vec3::new(PLAYER_SCALE, PLAYER_SCALE, 1.),
@@ -41,6 +54,8 @@ fn spawn_player_system(
                 ..default()
             },
             Player,
+            PlayerBody::default(),
+            Animator::default(),
             GameScreen,
         ));
 
@@ -48,41 +63,130 @@ fn spawn_player_system(
     }
 }
 
+// This is intended.
+#[allow(clippy::type_complexity)]
 fn move_player_system(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<&mut Transform, With<Player>>,
+    actions: Res<Actions>,
+    config: Res<GameConfig>,
+    mut query: Query<(&mut Transform, &mut PlayerBody), With<Player>>,
+    wall_query: Query<
+        (Entity, &Transform, Option<&Movement>, Option<&Conveyor>),
+        (With<Wall>, Without<Player>),
+    >,
+    ladder_query: Query<&Transform, (With<Ladder>, Without<Player>)>,
 ) {
-    if let Ok(mut player_transform) = query.get_single_mut() {
+    if let Ok((mut player_transform, mut player_body)) = query.get_single_mut() {
+        // Whatever the player stands on carries it along.
+        if let Some(Ok((_, _, movement, conveyor))) =
+            player_body.ground.map(|ground| wall_query.get(ground))
+        {
+            if let Some(movement) = movement {
+                player_transform.translation.x += movement.v_x * TIME_STEP;
+                player_transform.translation.y += movement.v_y * TIME_STEP;
+            }
+            if let Some(conveyor) = conveyor {
+                player_transform.translation.x += conveyor.speed * TIME_STEP;
+            }
+        }
+
         let mut direction = 0.0;
 
-        if keyboard_input.pressed(KeyCode::Left) {
+        if actions.pressed(Action::Left) {
             direction -= 1.0;
         }
 
-        if keyboard_input.pressed(KeyCode::Right) {
+        if actions.pressed(Action::Right) {
             direction += 1.0;
         }
 
         let new_player_position_x =
-            player_transform.translation.x + direction * PLAYER_SPEED * TIME_STEP;
+            player_transform.translation.x + direction * config.player.speed * TIME_STEP;
 
         let left_bound = LEFT + PLAYER_SIZE.0 / 2. * PLAYER_SCALE + WALL_SIZE / 2.;
 
         let right_bound = RIGHT - PLAYER_SIZE.0 / 2. * PLAYER_SCALE - WALL_SIZE / 2.;
 
         player_transform.translation.x = new_player_position_x.clamp(left_bound, right_bound);
+        player_body.run = direction;
+
+        let half_width = PLAYER_SIZE.0 / 2. * PLAYER_SCALE;
+        let half_height = PLAYER_SIZE.1 / 2. * PLAYER_SCALE;
+        let player_x = player_transform.translation.x;
+        let feet = player_transform.translation.y - half_height;
+
+        let ladder = ladder_query.iter().find(|ladder| {
+            (ladder.translation.x - player_x).abs() < ladder.scale.x / 2.
+                && (ladder.translation.y - feet).abs() <= ladder.scale.y / 2.
+        });
+
+        player_body.climbing = ladder.is_some();
+        let new_feet = if let Some(ladder) = ladder {
+            let mut climb = 0.0;
+
+            if actions.pressed(Action::Up) {
+                climb += 1.0;
+            }
+
+            if actions.pressed(Action::Down) {
+                climb -= 1.0;
+            }
+
+            let ladder_bottom = ladder.translation.y - ladder.scale.y / 2.;
+            let ladder_top = ladder.translation.y + ladder.scale.y / 2.;
+
+            player_body.v_y = 0.;
+            player_body.ground = None;
+
+            (feet + climb * config.player.climb_speed * TIME_STEP)
+                .clamp(ladder_bottom.max(BOTTOM + WALL_SIZE / 2.), ladder_top)
+        } else {
+            player_body.v_y = (player_body.v_y - config.player.gravity * TIME_STEP)
+                .max(-config.player.max_fall_speed);
+            let falling_feet = feet + player_body.v_y * TIME_STEP;
+
+            // The highest wall top under the player's feet is the ground it lands on.
+            let ground = wall_query
+                .iter()
+                .filter(|(_, wall, _, _)| {
+                    (wall.translation.x - player_x).abs() < wall.scale.x / 2. + half_width
+                        && wall.translation.y + wall.scale.y / 2.
+                            <= feet + config.player.ground_snap
+                })
+                .map(|(entity, wall, _, _)| (entity, wall.translation.y + wall.scale.y / 2.))
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+            let was_grounded = player_body.ground.is_some();
+            match ground {
+                Some((entity, top))
+                    if falling_feet <= top
+                        || (was_grounded && feet - top <= config.player.ground_snap) =>
+                {
+                    player_body.v_y = 0.;
+                    player_body.ground = Some(entity);
+                    top
+                }
+                _ => {
+                    player_body.ground = None;
+                    falling_feet
+                }
+            }
+        };
+
+        player_transform.translation.y = new_feet + half_height;
     }
 }
 
 fn shot_player_system(
     mut commands: Commands,
+    mut hook_fired_events: EventWriter<HookFiredEvent>,
     game_textures: Res<GameTextures>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
+    config: Res<GameConfig>,
     mut player_state: ResMut<PlayerState>,
     query: Query<&Transform, With<Player>>,
 ) {
     if let Ok(player_transform) = query.get_single() {
-        if keyboard_input.just_pressed(KeyCode::Space) && !player_state.hook_shoted {
+        if actions.pressed(Action::Shoot) && !player_state.hook_shoted {
             let (x_pos, y_pos) = (
                 player_transform.translation.x,
                 player_transform.translation.y,
@@ -104,12 +208,13 @@ fn shot_player_system(
                 Hook,
                 Movement {
                     v_x: 0.,
-                    v_y: HOOK_SPEED,
+                    v_y: config.player.hook_speed,
                     a: 0.,
                 },
                 GameScreen,
             ));
             player_state.shoot_hook();
+            hook_fired_events.send(HookFiredEvent);
         }
     }
 }
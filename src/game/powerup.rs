@@ -0,0 +1,222 @@
+use crate::game::bubble::{bubble_pop_system, BubbleAssets};
+use crate::game::components::{GameScreen, Movement, Player, PowerUp, PowerUpKind, Wall};
+use crate::game::config::{GameConfig, PowerUpConfig};
+use crate::game::events::{BubblePoppedEvent, Collider, CollisionEvent};
+use crate::game::{
+    reward_wall_collision_system, GameRng, PlayerState, SimulationStage, TickLabel, PLAYER_SIZE,
+    POWER_UP_SIZE, TIME_STEP,
+};
+use crate::{AppState, GameMode};
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use bevy::sprite::MaterialMesh2dBundle;
+use rand::Rng;
+use std::time::Duration;
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(power_up_setup_system))
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Movement.set().with_system(power_up_timer_system),
+            )
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Collisions
+                    .set()
+                    .with_system(power_up_wall_collision_system.after(reward_wall_collision_system))
+                    .with_system(
+                        power_up_player_collision_system.after(power_up_wall_collision_system),
+                    ),
+            )
+            .add_system_set_to_stage(
+                SimulationStage,
+                TickLabel::Reactions
+                    .set()
+                    .with_system(power_up_drop_system.after(bubble_pop_system)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ActivePowerUps {
+    active: Vec<(PowerUpKind, Timer)>,
+}
+
+impl ActivePowerUps {
+    // Picking up a power-up that's already running restarts it.
+    pub fn activate(&mut self, kind: PowerUpKind, config: &PowerUpConfig) {
+        self.active.retain(|(active_kind, _)| *active_kind != kind);
+        self.active.push((
+            kind,
+            Timer::from_seconds(config.duration(kind), TimerMode::Once),
+        ));
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.active
+            .iter()
+            .any(|(active_kind, _)| *active_kind == kind)
+    }
+
+    pub fn remaining(&self, kind: PowerUpKind) -> Option<f32> {
+        self.active
+            .iter()
+            .find(|(active_kind, _)| *active_kind == kind)
+            .map(|(_, timer)| timer.remaining_secs())
+    }
+
+    // How fast the wave timer of the endless modes runs.
+    pub fn wave_timer_scale(&self) -> f32 {
+        if self.is_active(PowerUpKind::Clock) {
+            0.
+        } else if self.is_active(PowerUpKind::Hourglass) {
+            0.5
+        } else {
+            1.
+        }
+    }
+}
+
+pub fn spawn_power_up(
+    commands: &mut Commands,
+    bubble_assets: &mut BubbleAssets,
+    config: &PowerUpConfig,
+    position: Vec3,
+    kind: PowerUpKind,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: bubble_assets.quad().into(),
+            material: bubble_assets.power_up_material(kind),
+            transform: Transform {
+                translation: position,
+                scale: Vec3::new(POWER_UP_SIZE, POWER_UP_SIZE, 0.),
+                ..default()
+            },
+            ..default()
+        },
+        Movement {
+            v_x: 0.,
+            v_y: -config.speed,
+            a: 0.,
+        },
+        PowerUp {
+            kind,
+            lifetime: Timer::from_seconds(config.lifetime, TimerMode::Once),
+        },
+        GameScreen,
+    ));
+}
+
+fn power_up_setup_system(mut commands: Commands) {
+    commands.insert_resource(ActivePowerUps::default());
+}
+
+pub fn power_up_timer_system(
+    mut commands: Commands,
+    mut active_power_ups: ResMut<ActivePowerUps>,
+    mut power_up_query: Query<(Entity, &mut PowerUp)>,
+) {
+    let delta = Duration::from_secs_f32(TIME_STEP);
+    for (_, timer) in active_power_ups.active.iter_mut() {
+        timer.tick(delta);
+    }
+    active_power_ups
+        .active
+        .retain(|(_, timer)| !timer.finished());
+
+    for (power_up_entity, mut power_up) in &mut power_up_query {
+        if power_up.lifetime.tick(delta).finished() {
+            commands.entity(power_up_entity).despawn();
+        }
+    }
+}
+
+// In panic mode popped bubbles sometimes leave a power-up behind.
+fn power_up_drop_system(
+    mut commands: Commands,
+    mut bubble_assets: BubbleAssets,
+    mut rng: ResMut<GameRng>,
+    game_mode: Res<GameMode>,
+    config: Res<GameConfig>,
+    mut bubble_popped_events: EventReader<BubblePoppedEvent>,
+) {
+    for bubble_popped in bubble_popped_events.iter() {
+        if *game_mode != GameMode::Panic || !rng.gen_bool(config.power_up.chance) {
+            continue;
+        }
+
+        let kind = if rng.gen_bool(0.5) {
+            PowerUpKind::Clock
+        } else {
+            PowerUpKind::Hourglass
+        };
+        spawn_power_up(
+            &mut commands,
+            &mut bubble_assets,
+            &config.power_up,
+            bubble_popped.position.extend(0.),
+            kind,
+        );
+    }
+}
+
+// Power-ups land on whatever they fall onto and wait there to be picked up.
+fn power_up_wall_collision_system(
+    mut power_up_query: Query<(&mut Movement, &Transform), With<PowerUp>>,
+    wall_query: Query<&Transform, With<Wall>>,
+) {
+    for (mut power_up_movement, power_up_transform) in power_up_query.iter_mut() {
+        for wall_transform in wall_query.iter() {
+            let collision = collide(
+                power_up_transform.translation,
+                power_up_transform.scale.truncate(),
+                wall_transform.translation,
+                wall_transform.scale.truncate(),
+            );
+
+            if collision.is_some() {
+                power_up_movement.v_y = 0.;
+                break;
+            }
+        }
+    }
+}
+
+fn power_up_player_collision_system(
+    mut commands: Commands,
+    player_state: Res<PlayerState>,
+    mut active_power_ups: ResMut<ActivePowerUps>,
+    config: Res<GameConfig>,
+    power_up_query: Query<(Entity, &Transform, &PowerUp)>,
+    player_query: Query<&Transform, With<Player>>,
+    mut collision_events: EventWriter<CollisionEvent>,
+) {
+    if player_state.is_alive {
+        if let Ok(player_transform) = player_query.get_single() {
+            for (power_up_entity, power_up_transform, power_up) in power_up_query.iter() {
+                let collision = collide(
+                    player_transform.translation,
+                    Vec2::new(
+                        player_transform.scale.x * PLAYER_SIZE.0,
+                        player_transform.scale.y * PLAYER_SIZE.1,
+                    ),
+                    power_up_transform.translation,
+                    power_up_transform.scale.truncate(),
+                );
+
+                if collision.is_some() {
+                    collision_events.send(CollisionEvent {
+                        first: Collider::Player,
+                        second: Collider::PowerUp,
+                    });
+                    commands.entity(power_up_entity).despawn();
+                    active_power_ups.activate(power_up.kind, &config.power_up);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,620 @@
+use crate::cli::Cli;
+use crate::game::components::{GameScreen, Player, ReplayText};
+use crate::game::config::{ConfigLabel, GameConfig};
+use crate::game::events::BubblePoppedEvent;
+use crate::game::ghost::{ghost_system, Ghost};
+use crate::game::input::{actions_system, Actions};
+use crate::game::level::LevelHandles;
+use crate::game::{
+    clock_system, Cheats, GameClock, GameRng, Simulation, SimulationStage, TickLabel, HUD_PADDING,
+    REPLAY_DIR, REPLAY_EXTENSION, REPLAY_INDEX_FILE, REPLAY_KEEP, REPLAY_NORMAL_SPEED,
+    REPLAY_SEEK_TICKS, REPLAY_SPEEDS, REPLAY_TEXT_COLOR, REPLAY_TEXT_SIZE, TIME_STEP,
+};
+use crate::settings::Accessibility;
+use crate::{AppState, Fonts, GameMode};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Game).with_system(replay_setup_system.after(ConfigLabel)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Input
+                .set()
+                .with_system(replay_actions_system.after(actions_system)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::GameEnd
+                .set()
+                .with_system(replay_track_system.after(clock_system).after(ghost_system)),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Game)
+                .with_system(replay_controls_system)
+                .with_system(replay_text_system.after(replay_controls_system)),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Game).with_system(replay_save_system))
+        .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(replay_end_system));
+    }
+}
+
+// Everything needed to play a run again: the game is deterministic given its seed, level,
+// mode and the player's actions in every tick.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+    pub version: String,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub level: String,
+    pub assist: bool,
+    // Runs of the same actions as (actions, ticks), holding a key takes a single entry.
+    inputs: Vec<(Actions, u32)>,
+    // Where the player was after every tick, for the ghost to follow.
+    #[serde(default)]
+    pub track: Vec<(i16, i16)>,
+    // Ticks of the clock at which each bubble was popped.
+    #[serde(default)]
+    pub pops: Vec<u32>,
+    // The config of the round, with the overrides of its mode and level.
+    #[serde(default)]
+    config: Option<GameConfig>,
+}
+
+impl Replay {
+    fn new(seed: u64, mode: GameMode, level: String, assist: bool, config: &GameConfig) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed,
+            mode,
+            level,
+            assist,
+            inputs: Vec::new(),
+            track: Vec::new(),
+            pops: Vec::new(),
+            config: Some(config.clone()),
+        }
+    }
+
+    // The config the run was recorded with, replays saved without one play with the current one.
+    pub fn config_or(&self, current: GameConfig) -> GameConfig {
+        let recorded = match &self.config {
+            Some(recorded) => recorded,
+            None => {
+                warn!("Replay recorded without its config, it may play out differently");
+                return current;
+            }
+        };
+        if let Err(error) = recorded.validate() {
+            warn!("Ignoring the invalid config of the replay: {}", error);
+            return current;
+        }
+        if ron::to_string(recorded).ok() != ron::to_string(&current).ok() {
+            warn!("Replay recorded with a different config, playing it with that one");
+        }
+        recorded.clone()
+    }
+
+    fn push(&mut self, actions: Actions) {
+        match self.inputs.last_mut() {
+            Some((last, ticks)) if *last == actions => *ticks += 1,
+            _ => self.inputs.push((actions, 1)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        let replay = match fs::read_to_string(path) {
+            Ok(replay) => replay,
+            Err(error) => {
+                warn!("Could not read {}: {}", path.display(), error);
+                return None;
+            }
+        };
+        match ron::from_str::<Self>(&replay) {
+            Ok(replay) => Some(replay),
+            Err(error) => {
+                warn!("Ignoring invalid replay {}: {}", path.display(), error);
+                None
+            }
+        }
+    }
+
+    fn paths() -> Vec<PathBuf> {
+        fs::read_dir(REPLAY_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.to_string_lossy().ends_with(REPLAY_EXTENSION))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Replays are named after when they were recorded, so the last one sorts last.
+    pub fn latest() -> Option<Self> {
+        Self::load(&Self::paths().into_iter().max()?)
+    }
+
+    // The run of the level that popped the most bubbles, the quicker one of those.
+    pub fn best(mode: GameMode, level: &str) -> Option<Self> {
+        let file = ReplayIndex::load().best(mode, level)?.file.clone();
+        Self::load(&Path::new(REPLAY_DIR).join(file))
+    }
+
+    fn save(&self) {
+        let replay = match ron::to_string(self) {
+            Ok(replay) => replay,
+            Err(error) => {
+                warn!("Could not serialize the replay: {}", error);
+                return;
+            }
+        };
+        let recorded = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let file = format!("{}.{}", recorded, REPLAY_EXTENSION);
+        let path = format!("{}/{}", REPLAY_DIR, file);
+        if let Err(error) = fs::create_dir_all(REPLAY_DIR).and_then(|_| fs::write(&path, replay)) {
+            warn!("Could not save {}: {}", path, error);
+            return;
+        }
+        let mut index = ReplayIndex::load();
+        index.insert(file, self);
+        index.save();
+        index.prune();
+    }
+}
+
+// The best replay of every mode and level, so starting a game doesn't read all of them.
+#[derive(Serialize, Deserialize, Default)]
+struct ReplayIndex {
+    levels: Vec<BestReplay>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BestReplay {
+    mode: GameMode,
+    level: String,
+    // Name of the replay in `REPLAY_DIR`.
+    file: String,
+    pops: usize,
+    // Tick of the last pop.
+    finished: Option<u32>,
+}
+
+impl BestReplay {
+    fn rank(&self) -> (usize, Reverse<Option<u32>>) {
+        (self.pops, Reverse(self.finished))
+    }
+}
+
+impl ReplayIndex {
+    // Replays saved before there was an index are read once to make one.
+    fn load() -> Self {
+        let path = Path::new(REPLAY_DIR).join(REPLAY_INDEX_FILE);
+        let index = match fs::read_to_string(&path) {
+            Ok(index) => index,
+            Err(_) => {
+                let index = Self::scan();
+                if !index.levels.is_empty() {
+                    index.save();
+                }
+                return index;
+            }
+        };
+        match ron::from_str::<Self>(&index) {
+            Ok(index) => index,
+            Err(error) => {
+                warn!("Ignoring invalid {}: {}", path.display(), error);
+                Self::scan()
+            }
+        }
+    }
+
+    fn scan() -> Self {
+        let mut index = Self::default();
+        for path in Replay::paths() {
+            if let (Some(replay), Some(file)) = (Replay::load(&path), path.file_name()) {
+                index.insert(file.to_string_lossy().into_owned(), &replay);
+            }
+        }
+        index
+    }
+
+    fn save(&self) {
+        let path = Path::new(REPLAY_DIR).join(REPLAY_INDEX_FILE);
+        let index = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(index) => index,
+            Err(error) => {
+                warn!("Could not serialize the replay index: {}", error);
+                return;
+            }
+        };
+        if let Err(error) = fs::write(&path, index) {
+            warn!("Could not save {}: {}", path.display(), error);
+        }
+    }
+
+    // Deletes the replays older than the latest `REPLAY_KEEP` that aren't the best of a level.
+    fn prune(&self) {
+        let mut paths = Replay::paths();
+        paths.sort();
+        let old = paths.len().saturating_sub(REPLAY_KEEP);
+        for path in &paths[..old] {
+            let best = path.file_name().is_some_and(|file| {
+                self.levels
+                    .iter()
+                    .any(|best| best.file == file.to_string_lossy())
+            });
+            if !best {
+                if let Err(error) = fs::remove_file(path) {
+                    warn!("Could not delete {}: {}", path.display(), error);
+                }
+            }
+        }
+    }
+
+    fn best(&self, mode: GameMode, level: &str) -> Option<&BestReplay> {
+        self.levels
+            .iter()
+            .find(|best| best.mode == mode && best.level == level)
+    }
+
+    // Keeps the replay if it beats the best one of its level, only one with a track can be
+    // raced against.
+    fn insert(&mut self, file: String, replay: &Replay) {
+        if replay.track.is_empty() {
+            return;
+        }
+        let best = BestReplay {
+            mode: replay.mode,
+            level: replay.level.clone(),
+            file,
+            pops: replay.pops.len(),
+            finished: replay.pops.last().copied(),
+        };
+        match self
+            .levels
+            .iter_mut()
+            .find(|old| old.mode == best.mode && old.level == best.level)
+        {
+            Some(old) if old.rank() < best.rank() => *old = best,
+            Some(_) => {}
+            None => self.levels.push(best),
+        }
+    }
+}
+
+// The run being played, saved as a replay when it ends.
+#[derive(Resource)]
+pub struct Recording(Replay);
+
+// Present while a replay is watched instead of a game played.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    actions: Vec<Actions>,
+    tick: usize,
+    paused: bool,
+    speed: usize,
+    // Going back replays the run from the start up to this tick.
+    seek: Option<usize>,
+    // The viewer asked to play against the replay as a ghost.
+    race: bool,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        if replay.version != env!("CARGO_PKG_VERSION") {
+            warn!(
+                "Replay recorded with version {}, it may play out differently",
+                replay.version
+            );
+        }
+        let actions = replay
+            .inputs
+            .iter()
+            .flat_map(|&(actions, ticks)| std::iter::repeat_n(actions, ticks as usize))
+            .collect();
+        Self {
+            replay,
+            actions,
+            tick: 0,
+            paused: false,
+            speed: REPLAY_NORMAL_SPEED,
+            seek: None,
+            race: false,
+        }
+    }
+
+    // False once the viewer turned it into a race, the next round is played.
+    pub fn is_watched(&self) -> bool {
+        !self.race
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.actions.len()
+    }
+}
+
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn replay_setup_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    asset_server: Res<AssetServer>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+    accessibility: Res<Accessibility>,
+    cli: Res<Cli>,
+    config: Res<GameConfig>,
+    replay_playback: Option<ResMut<ReplayPlayback>>,
+) {
+    let level = level_handles.path(&asset_server, *game_mode);
+
+    let seed = match replay_playback {
+        Some(mut replay_playback) if replay_playback.is_watched() => {
+            if replay_playback.replay.level != level {
+                warn!(
+                    "Replay recorded on {}, playing it on {}",
+                    replay_playback.replay.level, level
+                );
+            }
+            replay_playback.tick = 0;
+            commands.spawn((
+                TextBundle::from_section(
+                    String::new(),
+                    TextStyle {
+                        font: fonts.default.clone(),
+                        font_size: REPLAY_TEXT_SIZE,
+                        color: REPLAY_TEXT_COLOR,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(HUD_PADDING),
+                        bottom: Val::Px(HUD_PADDING),
+                        ..default()
+                    },
+                    ..default()
+                }),
+                ReplayText,
+                GameScreen,
+            ));
+            replay_playback.replay.seed
+        }
+        replay_playback => {
+            // A replay raced against from the viewer, otherwise the best run of this level
+            // unless the seed was given on the command line.
+            let ghost = match replay_playback {
+                Some(replay_playback) => {
+                    commands.remove_resource::<ReplayPlayback>();
+                    Some(replay_playback.replay.clone())
+                }
+                None if cli.seed.is_none() => Replay::best(*game_mode, &level),
+                None => None,
+            };
+            // Both runs start out facing the same bubbles.
+            let seed = ghost
+                .as_ref()
+                .map(|ghost| ghost.seed)
+                .or(cli.seed)
+                .unwrap_or_else(rand::random);
+            if let Some(ghost) = ghost {
+                commands.insert_resource(Ghost::new(ghost));
+            }
+            commands.insert_resource(Recording(Replay::new(
+                seed,
+                *game_mode,
+                level,
+                accessibility.assist,
+                &config,
+            )));
+            seed
+        }
+    };
+    commands.insert_resource(GameRng(StdRng::seed_from_u64(seed)));
+}
+
+// Records the actions of this tick, or replaces them with the recorded ones.
+fn replay_actions_system(
+    mut actions: ResMut<Actions>,
+    recording: Option<ResMut<Recording>>,
+    replay_playback: Option<ResMut<ReplayPlayback>>,
+) {
+    if let Some(mut replay_playback) = replay_playback {
+        *actions = replay_playback
+            .actions
+            .get(replay_playback.tick)
+            .copied()
+            .unwrap_or_default();
+        replay_playback.tick += 1;
+    } else if let Some(mut recording) = recording {
+        recording.0.push(*actions);
+    }
+}
+
+// Where the player is and what it popped, once the tick is over.
+fn replay_track_system(
+    clock: Res<GameClock>,
+    recording: Option<ResMut<Recording>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut bubble_popped_events: EventReader<BubblePoppedEvent>,
+) {
+    let mut recording = match recording {
+        Some(recording) => recording,
+        None => return,
+    };
+    // A dead player stays where it was hit until it spawns again.
+    let position = match player_query.get_single() {
+        Ok(transform) => (
+            transform.translation.x as i16,
+            transform.translation.y as i16,
+        ),
+        Err(_) => recording.0.track.last().copied().unwrap_or_default(),
+    };
+    recording.0.track.push(position);
+    for _ in bubble_popped_events.iter() {
+        recording.0.pops.push(clock.ticks as u32);
+    }
+}
+
+// P pauses, . steps a tick while paused, up and down change the speed, left and right jump
+// back and forward, R races against it, escape stops watching.
+fn replay_controls_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game_state: ResMut<State<AppState>>,
+    mut simulation: ResMut<Simulation>,
+    replay_playback: Option<ResMut<ReplayPlayback>>,
+) {
+    let mut replay_playback = match replay_playback {
+        Some(replay_playback) if replay_playback.is_watched() => replay_playback,
+        _ => return,
+    };
+    let length = replay_playback.actions.len();
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(AppState::Menu).unwrap();
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::P) {
+        replay_playback.paused = !replay_playback.paused;
+    }
+    if keyboard_input.just_pressed(KeyCode::Period) && replay_playback.paused {
+        simulation.step = true;
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        replay_playback.speed = (replay_playback.speed + 1).min(REPLAY_SPEEDS.len() - 1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        replay_playback.speed = replay_playback.speed.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        let target = (replay_playback.tick + REPLAY_SEEK_TICKS).min(length);
+        simulation.fast_forward = target - replay_playback.tick;
+    }
+    if keyboard_input.just_pressed(KeyCode::R) {
+        replay_playback.race = true;
+        game_state.restart().unwrap();
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        replay_playback.seek = Some(replay_playback.tick.saturating_sub(REPLAY_SEEK_TICKS));
+        game_state.restart().unwrap();
+        return;
+    }
+
+    // Back at the start after a restart, run up to where the viewer jumped to.
+    if let Some(seek) = replay_playback.seek.take() {
+        simulation.fast_forward = seek;
+    }
+    // The end of the recording holds the game still.
+    simulation.paused = replay_playback.paused || replay_playback.tick >= length;
+    simulation.speed = REPLAY_SPEEDS[replay_playback.speed];
+}
+
+fn replay_text_system(
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut text_query: Query<&mut Text, With<ReplayText>>,
+) {
+    if let Some(replay_playback) = replay_playback {
+        let length = replay_playback.actions.len();
+        let state = if replay_playback.tick >= length {
+            "End"
+        } else if replay_playback.paused {
+            "Paused"
+        } else {
+            ""
+        };
+        for mut text in &mut text_query {
+            text.sections[0].value = format!(
+                "Replay  {:.2} / {:.2} s  x{}  {}\nP pause   . step   Up/Down speed   Left/Right seek   R race   Esc quit",
+                replay_playback.tick as f32 * TIME_STEP,
+                length as f32 * TIME_STEP,
+                REPLAY_SPEEDS[replay_playback.speed],
+                state,
+            );
+        }
+    }
+}
+
+fn replay_save_system(
+    mut commands: Commands,
+    cheats: Res<Cheats>,
+    recording: Option<Res<Recording>>,
+) {
+    if let Some(recording) = recording {
+        // A level tested from the editor has no file to play the replay on, the cheats of a
+        // cheated run aren't recorded so it would play out differently.
+        if !recording.0.inputs.is_empty() && !recording.0.level.is_empty() && !cheats.cheated() {
+            recording.0.save();
+        }
+        commands.remove_resource::<Recording>();
+    }
+}
+
+fn replay_end_system(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::input::Action;
+
+    fn actions(pressed: &[Action]) -> Actions {
+        let mut actions = Actions::default();
+        for &action in pressed {
+            actions.press(action);
+        }
+        actions
+    }
+
+    #[test]
+    fn push_merges_repeated_actions() {
+        let mut replay = Replay::new(
+            0,
+            GameMode::Campaign,
+            String::new(),
+            false,
+            &GameConfig::default(),
+        );
+        let idle = actions(&[]);
+        let left = actions(&[Action::Left]);
+        let left_shoot = actions(&[Action::Left, Action::Shoot]);
+        for actions in [idle, idle, left, left, left, left_shoot, left, idle] {
+            replay.push(actions);
+        }
+        assert_eq!(
+            replay.inputs,
+            vec![(idle, 2), (left, 3), (left_shoot, 1), (left, 1), (idle, 1)]
+        );
+    }
+
+    #[test]
+    fn push_starts_empty_replay() {
+        let mut replay = Replay::new(
+            0,
+            GameMode::Survival,
+            String::new(),
+            false,
+            &GameConfig::default(),
+        );
+        assert!(replay.inputs.is_empty());
+        replay.push(actions(&[Action::Shoot]));
+        assert_eq!(replay.inputs, vec![(actions(&[Action::Shoot]), 1)]);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::game::config::{ConfigLabel, GameConfig};
+use crate::game::wave::{BubbleDrops, WaveConfig, WaveSpawner};
+use crate::game::{CurrentLevel, SimulationStage, TickLabel, TIME_STEP};
+use crate::{AppState, GameMode};
+use bevy::prelude::*;
+
+pub struct SurvivalPlugin;
+
+impl Plugin for SurvivalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Game)
+                .with_system(survival_setup_system.after(ConfigLabel)),
+        )
+        .add_system_set_to_stage(
+            SimulationStage,
+            TickLabel::Movement.set().with_system(survival_spawn_system),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct SurvivalState {
+    // Every dropped bubble starts the next wave.
+    waves: WaveSpawner,
+}
+
+fn survival_setup_system(mut commands: Commands, config: Res<GameConfig>) {
+    commands.insert_resource(SurvivalState {
+        waves: WaveSpawner::new(&WaveConfig::from(&config.survival)),
+    });
+}
+
+// Drops a new bubble in from the top whenever the wave timer runs out.
+pub fn survival_spawn_system(
+    mut survival_state: ResMut<SurvivalState>,
+    mut drops: BubbleDrops,
+    mut current_level: ResMut<CurrentLevel>,
+    game_mode: Res<GameMode>,
+    config: Res<GameConfig>,
+) {
+    if *game_mode != GameMode::Survival {
+        return;
+    }
+
+    let wave_config = WaveConfig::from(&config.survival);
+    let waves = &mut survival_state.waves;
+    if waves.tick(TIME_STEP, &wave_config, &config.bubble, &mut drops) {
+        waves.step += 1;
+        waves.restart(&wave_config);
+        current_level.number = waves.step;
+    }
+}
@@ -0,0 +1,236 @@
+use crate::game::components::{BubbleKind, PowerUpKind};
+use crate::game::level::{Level, LevelHandles};
+use crate::game::{
+    BALL_COLOR, BALL_OUTLINE_COLOR, BALL_SHINE_COLOR, BOUNCY_BALL_COLOR, BREAKABLE_COLOR,
+    CLOCK_COLOR, COLORBLIND_THEME_FILE, CONVEYOR_COLOR, DEFAULT_THEME_FILE, FAST_BALL_COLOR,
+    GLASS_COLOR, HIGH_CONTRAST_THEME_FILE, HOURGLASS_COLOR, LADDER_COLOR, REWARD_COLOR, WALL_COLOR,
+};
+use crate::settings::Accessibility;
+use crate::{AppState, Fonts, GameMode, BACKGROUND_COLOR, TEXT_FONT_FILE};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .insert_resource(Palette::default())
+            .insert_resource(ThemeImages::default())
+            .add_startup_system(theme_handles_system)
+            .add_system(level_theme_system)
+            .add_system_set(
+                SystemSet::on_enter(AppState::Game).with_system(theme_system.label(ThemeLabel)),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Game).with_system(theme_reset_system));
+    }
+}
+
+// Systems spawning anything coloured on entering the game run after the theme is picked.
+#[derive(SystemLabel)]
+pub struct ThemeLabel;
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "9f0e2d7a-3c41-4b8e-8a56-1d2c7e4b9a30"]
+pub struct Theme {
+    #[serde(default)]
+    palette: Palette,
+    #[serde(default)]
+    background: Option<String>,
+    #[serde(default)]
+    wall_texture: Option<String>,
+    #[serde(default)]
+    font: Option<String>,
+}
+
+// Colours of the current theme, anything left out of a theme file keeps its default.
+#[derive(Resource, Deserialize, Clone)]
+#[serde(default)]
+pub struct Palette {
+    pub background: Color,
+    pub wall: Color,
+    pub bubble: Color,
+    pub fast_bubble: Color,
+    pub bouncy_bubble: Color,
+    // Colour of the highlight on the bubbles, its alpha is how strong it is.
+    pub shine: Color,
+    // Ring around the bubbles, a zero alpha turns it off.
+    pub outline: Color,
+    pub reward: Color,
+    pub breakable: Color,
+    pub glass: Color,
+    pub ladder: Color,
+    pub conveyor: Color,
+    pub clock: Color,
+    pub hourglass: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: BACKGROUND_COLOR,
+            wall: WALL_COLOR,
+            bubble: BALL_COLOR,
+            fast_bubble: FAST_BALL_COLOR,
+            bouncy_bubble: BOUNCY_BALL_COLOR,
+            shine: BALL_SHINE_COLOR,
+            outline: BALL_OUTLINE_COLOR,
+            reward: REWARD_COLOR,
+            breakable: BREAKABLE_COLOR,
+            glass: GLASS_COLOR,
+            ladder: LADDER_COLOR,
+            conveyor: CONVEYOR_COLOR,
+            clock: CLOCK_COLOR,
+            hourglass: HOURGLASS_COLOR,
+        }
+    }
+}
+
+impl Palette {
+    pub fn bubble(&self, kind: BubbleKind) -> Color {
+        match kind {
+            BubbleKind::Normal => self.bubble,
+            BubbleKind::Fast => self.fast_bubble,
+            BubbleKind::Bouncy => self.bouncy_bubble,
+        }
+    }
+
+    pub fn power_up(&self, kind: PowerUpKind) -> Color {
+        match kind {
+            PowerUpKind::Clock => self.clock,
+            PowerUpKind::Hourglass => self.hourglass,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ThemeImages {
+    pub background: Option<Handle<Image>>,
+    pub wall: Option<Handle<Image>>,
+}
+
+#[derive(Resource)]
+struct ThemeHandles {
+    default: Handle<Theme>,
+    colorblind: Handle<Theme>,
+    high_contrast: Handle<Theme>,
+    // Themes picked by levels, loaded together with the level.
+    levels: HashMap<String, Handle<Theme>>,
+}
+
+#[derive(Default)]
+struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let theme = ron::de::from_bytes::<Theme>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+fn theme_handles_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ThemeHandles {
+        default: asset_server.load(DEFAULT_THEME_FILE),
+        colorblind: asset_server.load(COLORBLIND_THEME_FILE),
+        high_contrast: asset_server.load(HIGH_CONTRAST_THEME_FILE),
+        levels: HashMap::new(),
+    });
+}
+
+fn level_theme_system(
+    mut level_events: EventReader<AssetEvent<Level>>,
+    levels: Res<Assets<Level>>,
+    asset_server: Res<AssetServer>,
+    mut theme_handles: ResMut<ThemeHandles>,
+) {
+    for event in level_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            if let Some(theme) = levels.get(handle).and_then(|level| level.theme.as_ref()) {
+                if !theme_handles.levels.contains_key(theme) {
+                    let theme_handle = asset_server.load(theme.as_str());
+                    theme_handles.levels.insert(theme.clone(), theme_handle);
+                }
+            }
+        }
+    }
+}
+
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn theme_system(
+    asset_server: Res<AssetServer>,
+    themes: Res<Assets<Theme>>,
+    theme_handles: Res<ThemeHandles>,
+    accessibility: Res<Accessibility>,
+    levels: Res<Assets<Level>>,
+    level_handles: Res<LevelHandles>,
+    game_mode: Res<GameMode>,
+    mut palette: ResMut<Palette>,
+    mut theme_images: ResMut<ThemeImages>,
+    mut clear_color: ResMut<ClearColor>,
+    mut fonts: ResMut<Fonts>,
+) {
+    let level_theme = levels
+        .get(level_handles.current(*game_mode))
+        .and_then(|level| level.theme.as_ref())
+        .and_then(|theme| theme_handles.levels.get(theme));
+    // The accessibility themes win over whatever theme the level picks, the high-contrast
+    // one is colour-blind safe as well.
+    let handle = if accessibility.high_contrast {
+        &theme_handles.high_contrast
+    } else if accessibility.colorblind {
+        &theme_handles.colorblind
+    } else {
+        level_theme.unwrap_or(&theme_handles.default)
+    };
+
+    // A theme that isn't loaded yet falls back to the default look.
+    let theme = match themes.get(handle) {
+        Some(theme) => theme,
+        None => {
+            *palette = Palette::default();
+            *theme_images = ThemeImages::default();
+            return;
+        }
+    };
+
+    *palette = theme.palette.clone();
+    clear_color.0 = palette.background;
+    theme_images.background = theme
+        .background
+        .as_ref()
+        .map(|background| asset_server.load(background.as_str()));
+    theme_images.wall = theme
+        .wall_texture
+        .as_ref()
+        .map(|wall_texture| asset_server.load(wall_texture.as_str()));
+    if let Some(font) = &theme.font {
+        fonts.default = asset_server.load(font.as_str());
+    }
+}
+
+fn theme_reset_system(
+    asset_server: Res<AssetServer>,
+    accessibility: Res<Accessibility>,
+    mut clear_color: ResMut<ClearColor>,
+    mut fonts: ResMut<Fonts>,
+) {
+    clear_color.0 = accessibility.background_color();
+    fonts.default = asset_server.load(TEXT_FONT_FILE);
+}
@@ -0,0 +1,146 @@
+use crate::game::bubble::{drop_bubble, BubbleAssets};
+use crate::game::components::BubbleKind;
+use crate::game::config::{BubbleConfig, PanicConfig, SurvivalConfig};
+use crate::game::{BubbleState, GameRng};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+// How the bubbles dropped in from the top grow with every step, a wave in survival and a level
+// in panic.
+pub struct WaveConfig {
+    first_drop: f32,
+    interval_start: f32,
+    interval_decay: f32,
+    interval_min: f32,
+    speed_step: f32,
+    speed_max: f32,
+    // Every this many steps the biggest dropped bubble grows by one size.
+    size_steps: usize,
+    size_max: usize,
+    fast_step: usize,
+    bouncy_step: usize,
+}
+
+impl From<&SurvivalConfig> for WaveConfig {
+    fn from(config: &SurvivalConfig) -> Self {
+        Self {
+            first_drop: config.first_drop,
+            interval_start: config.interval_start,
+            interval_decay: config.interval_decay,
+            interval_min: config.interval_min,
+            speed_step: config.speed_step,
+            speed_max: config.speed_max,
+            size_steps: config.size_waves,
+            size_max: config.size_max,
+            fast_step: config.fast_wave,
+            bouncy_step: config.bouncy_wave,
+        }
+    }
+}
+
+// Panic levels count from 1, steps from 0.
+impl From<&PanicConfig> for WaveConfig {
+    fn from(config: &PanicConfig) -> Self {
+        Self {
+            first_drop: config.first_drop,
+            interval_start: config.interval_start,
+            interval_decay: config.interval_decay,
+            interval_min: config.interval_min,
+            speed_step: config.speed_step,
+            speed_max: f32::INFINITY,
+            size_steps: config.size_levels,
+            size_max: config.size_max,
+            fast_step: config.fast_level.saturating_sub(1),
+            bouncy_step: config.bouncy_level.saturating_sub(1),
+        }
+    }
+}
+
+// Everything a dropped bubble is added to the round with.
+#[derive(SystemParam)]
+pub(super) struct BubbleDrops<'w, 's> {
+    commands: Commands<'w, 's>,
+    bubble_assets: BubbleAssets<'w, 's>,
+    bubble_state: ResMut<'w, BubbleState>,
+    rng: ResMut<'w, GameRng>,
+}
+
+// Drops a bubble whenever its timer runs out, the mode decides when the step goes up.
+pub struct WaveSpawner {
+    pub step: usize,
+    timer: Timer,
+}
+
+impl WaveSpawner {
+    pub fn new(config: &WaveConfig) -> Self {
+        Self {
+            step: 0,
+            timer: Timer::from_seconds(config.first_drop, TimerMode::Once),
+        }
+    }
+
+    fn interval(&self, config: &WaveConfig) -> f32 {
+        (config.interval_start * config.interval_decay.powi(self.step as i32))
+            .max(config.interval_min)
+    }
+
+    fn speed(&self, config: &WaveConfig) -> f32 {
+        (1. + config.speed_step * self.step as f32).min(config.speed_max)
+    }
+
+    fn max_size(&self, config: &WaveConfig) -> usize {
+        (2 + self.step / config.size_steps).min(config.size_max)
+    }
+
+    fn kinds(&self, config: &WaveConfig) -> Vec<BubbleKind> {
+        let mut kinds = vec![BubbleKind::Normal];
+        if self.step >= config.fast_step {
+            kinds.push(BubbleKind::Fast);
+        }
+        if self.step >= config.bouncy_step {
+            kinds.push(BubbleKind::Bouncy);
+        }
+        kinds
+    }
+
+    // Ticks the timer by `delta` seconds once the level's own bubbles are in, and drops a bubble
+    // of the current step when it runs out. Returns whether one was dropped.
+    pub fn tick(
+        &mut self,
+        delta: f32,
+        config: &WaveConfig,
+        bubble_config: &BubbleConfig,
+        drops: &mut BubbleDrops,
+    ) -> bool {
+        if !drops.bubble_state.spawned {
+            return false;
+        }
+
+        self.timer.tick(Duration::from_secs_f32(delta));
+        if !self.timer.finished() {
+            return false;
+        }
+
+        let size = drops.rng.gen_range(2..=self.max_size(config)) as f32;
+        let kinds = self.kinds(config);
+        let kind = kinds[drops.rng.gen_range(0..kinds.len())];
+        drop_bubble(
+            &mut drops.commands,
+            &mut drops.bubble_assets,
+            bubble_config,
+            &mut drops.rng,
+            size,
+            kind,
+            self.speed(config),
+        );
+        drops.bubble_state.count += 1;
+        true
+    }
+
+    // Waits for the next drop as long as the current step asks for.
+    pub fn restart(&mut self, config: &WaveConfig) {
+        self.timer = Timer::from_seconds(self.interval(config), TimerMode::Once);
+    }
+}
@@ -1,16 +1,32 @@
+use audio::SoundPlugin;
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::utils::HashMap;
+use bevy::winit::WinitPlugin;
+use camera::CameraPlugin;
+use cli::Cli;
 use game::GamePlugin;
 use menu::MenuPlugin;
+use records::RecordsPlugin;
+use serde::{Deserialize, Serialize};
+use settings::SettingsPlugin;
 use splash::SplashPlugin;
 
+mod audio;
+mod camera;
+mod cli;
 mod game;
 mod menu;
+mod records;
+mod settings;
 mod splash;
 
 // WINDOW CONFIGURATION
 const GAME_NAME: &str = "Bubble Rustle!";
-const WINDOW_MODE: WindowMode = WindowMode::Fullscreen;
-const RESIZABLE: bool = false;
+const WINDOW_MODE: WindowMode = WindowMode::Windowed;
+const WINDOW_SIZE: (f32, f32) = (1280., 720.);
+const RESIZABLE: bool = true;
 
 // COLOR
 const BACKGROUND_COLOR: Color = Color::rgb(0.5, 0.45, 0.5);
@@ -21,7 +37,56 @@ const TEXT_FONT_FILE: &str = "FiraSans-Bold.ttf";
 // RESOURCES
 #[derive(Resource, Default)]
 struct Scores {
-    score_list: Vec<usize>,
+    score_lists: HashMap<GameMode, Vec<usize>>,
+}
+
+impl Scores {
+    fn push(&mut self, game_mode: GameMode, score: usize) {
+        self.score_lists.entry(game_mode).or_default().push(score);
+    }
+
+    fn top(&self, game_mode: GameMode, count: usize) -> Vec<usize> {
+        let mut score_list = self
+            .score_lists
+            .get(&game_mode)
+            .cloned()
+            .unwrap_or_default();
+        score_list.sort_unstable_by(|a, b| b.cmp(a));
+        score_list.truncate(count);
+        score_list
+    }
+}
+
+// Every mode keeps its own high-score table.
+#[derive(Resource, Clone, Copy, Eq, PartialEq, Debug, Hash, Default, Serialize, Deserialize)]
+enum GameMode {
+    #[default]
+    Campaign,
+    Survival,
+    Panic,
+    TimeAttack,
+}
+
+impl GameMode {
+    // Time attack keeps best times instead of scores.
+    const SCORED: [GameMode; 3] = [GameMode::Campaign, GameMode::Survival, GameMode::Panic];
+
+    fn name(&self) -> &'static str {
+        match self {
+            GameMode::Campaign => "Campaign",
+            GameMode::Survival => "Survival",
+            GameMode::Panic => "Panic",
+            GameMode::TimeAttack => "Time Attack",
+        }
+    }
+
+    // Whether popping the last bubble finishes the game.
+    fn ends_when_cleared(&self) -> bool {
+        match self {
+            GameMode::Campaign | GameMode::TimeAttack => true,
+            GameMode::Survival | GameMode::Panic => false,
+        }
+    }
 }
 
 #[derive(Resource)]
@@ -34,6 +99,7 @@ enum AppState {
     Splash,
     Menu,
     Game,
+    Editor,
 }
 
 fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
@@ -50,22 +116,53 @@ fn fonts_system(mut commands: Commands, asset_server: Res<AssetServer>) {
 }
 
 fn main() {
-    App::new()
-        .add_startup_system(fonts_system)
-        .insert_resource(ClearColor(BACKGROUND_COLOR))
-        .insert_resource(Scores::default())
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
+    let cli = Cli::parse();
+    let (width, height) = cli.resolution.unwrap_or(WINDOW_SIZE);
+    let plugins = DefaultPlugins
+        .set(WindowPlugin {
             window: WindowDescriptor {
                 title: GAME_NAME.to_string(),
-                mode: WINDOW_MODE,
+                mode: cli.window_mode.unwrap_or(WINDOW_MODE),
+                width,
+                height,
                 resizable: RESIZABLE,
                 ..Default::default()
             },
+            // Without a window the app quits on its own.
+            add_primary_window: !cli.headless,
+            exit_on_all_closed: !cli.headless,
+            ..Default::default()
+        })
+        // Edited assets like the game config apply without a restart.
+        .set(AssetPlugin {
+            watch_for_changes: true,
             ..Default::default()
-        }))
+        });
+
+    let mut app = App::new();
+    if cli.headless {
+        // Nothing is drawn, so no graphics device is needed either.
+        app.insert_resource(WgpuSettings {
+            backends: None,
+            ..Default::default()
+        })
+        .add_plugins(plugins.disable::<WinitPlugin>())
+        .add_plugin(ScheduleRunnerPlugin);
+    } else {
+        app.add_plugins(plugins);
+    }
+    app.insert_resource(cli)
+        .add_startup_system(fonts_system)
+        .insert_resource(ClearColor(BACKGROUND_COLOR))
+        .insert_resource(Scores::default())
+        .insert_resource(GameMode::default())
         .add_state(AppState::Splash)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(RecordsPlugin)
+        .add_plugin(CameraPlugin)
         .add_plugin(SplashPlugin)
         .add_plugin(MenuPlugin)
         .add_plugin(GamePlugin)
+        .add_plugin(SoundPlugin)
         .run();
 }
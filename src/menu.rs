@@ -1,4 +1,9 @@
-use crate::{despawn_screen, AppState, Fonts, Scores, GAME_NAME};
+use crate::audio::{VolumeBus, VolumeSettings};
+use crate::game::level::{Level, LevelHandles, Medal};
+use crate::game::replay::{Replay, ReplayPlayback};
+use crate::records::{format_time, Records};
+use crate::settings::Accessibility;
+use crate::{despawn_screen, AppState, Fonts, GameMode, Scores, GAME_NAME};
 use bevy::app::AppExit;
 use bevy::prelude::*;
 
@@ -8,24 +13,52 @@ const MENU_MARGIN_PX: f32 = 50.0;
 
 const BUTTON_SIZE_PX: (f32, f32) = (250.0, 65.0);
 const BUTTON_MARGIN_PX: f32 = 20.0;
+const MENU_COLUMNS: f32 = 2.0;
+const LEVEL_BUTTON_SIZE_PX: (f32, f32) = (800.0, 65.0);
+const SETTING_BUTTON_SIZE_PX: (f32, f32) = (600.0, 65.0);
+const STEP_BUTTON_SIZE_PX: (f32, f32) = (65.0, 65.0);
 
 const TEXT_COLOR: Color = Color::rgb(1.0, 1.0, 1.0);
 
 const TEXT_PLAY_BUTTON: &str = "Let's Play!";
+const TEXT_SURVIVAL_BUTTON: &str = "Survival";
+const TEXT_PANIC_BUTTON: &str = "Panic";
+const TEXT_TIME_ATTACK_BUTTON: &str = "Time Attack";
 const TEXT_QUIT_BUTTON: &str = "Quit!";
 const TEXT_SCORES_BUTTON: &str = "Scores";
+const TEXT_SETTINGS_BUTTON: &str = "Settings";
+const TEXT_REPLAY_BUTTON: &str = "Last replay";
+const TEXT_EDITOR_BUTTON: &str = "Editor";
+const TEXT_SETTINGS: &str = "Settings";
+const TEXT_LESS_BUTTON: &str = "-";
+const TEXT_MORE_BUTTON: &str = "+";
 const TEXT_SCORES: &str = "Top scores of all time:";
 const TEXT_NO_SCORES: &str = "There are no scores yet!";
 const TEXT_BACK_MENU: &str = "Back to menu!";
+const TEXT_NO_TIME: &str = "-:--.---";
+const TEXT_LOCKED: &str = "Locked";
+const TEXT_STAR: &str = "*";
 
 const TEXT_TITLE_SIZE: f32 = 80.0;
 const TEXT_BUTTON_SIZE: f32 = 45.0;
 const TEXT_SCORE_SIZE: f32 = 60.0;
+const TEXT_MODE_SIZE: f32 = 45.0;
+
+const TOP_SCORES_COUNT: usize = 5;
+const MAX_STARS: usize = 3;
 
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+const LOCKED_TEXT_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+const GOLD_COLOR: Color = Color::rgb(1.0, 0.84, 0.0);
+const SILVER_COLOR: Color = Color::rgb(0.75, 0.75, 0.8);
+const BRONZE_COLOR: Color = Color::rgb(0.8, 0.5, 0.2);
+const HIGH_CONTRAST_NORMAL_BUTTON: Color = Color::BLACK;
+const HIGH_CONTRAST_HOVERED_BUTTON: Color = Color::rgb(0.4, 0.4, 0.4);
+const HIGH_CONTRAST_HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.0, 0.35, 0.55);
+const HIGH_CONTRAST_PRESSED_BUTTON: Color = Color::rgb(0.0, 0.45, 0.7);
 
 #[derive(Component)]
 struct MainScreen;
@@ -33,17 +66,84 @@ struct MainScreen;
 #[derive(Component)]
 struct ScoresScreen;
 
+#[derive(Component)]
+struct SettingsScreen;
+
+#[derive(Component)]
+struct LevelSelectScreen;
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 enum MenuState {
     Disabled,
     Scores,
+    Settings,
+    LevelSelect,
     Main,
 }
 
+#[derive(Component, Clone, Copy)]
+enum Setting {
+    Colorblind,
+    HighContrast,
+    ReducedMotion,
+    GameSpeed,
+    Assist,
+    Volume(VolumeBus),
+}
+
+impl Setting {
+    fn label(&self, accessibility: &Accessibility, volume: &VolumeSettings) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        match self {
+            Setting::Colorblind => {
+                format!("Colour-blind palette: {}", on_off(accessibility.colorblind))
+            }
+            Setting::HighContrast => {
+                format!("High contrast: {}", on_off(accessibility.high_contrast))
+            }
+            Setting::ReducedMotion => {
+                format!("Reduced motion: {}", on_off(accessibility.reduced_motion))
+            }
+            Setting::GameSpeed => format!("Game speed: {}%", accessibility.game_speed),
+            Setting::Assist => format!("Assist lives: {}", on_off(accessibility.assist)),
+            Setting::Volume(bus) => {
+                let name = match bus {
+                    VolumeBus::Master => "Volume",
+                    VolumeBus::Music => "Music",
+                    VolumeBus::Effects => "Effects",
+                };
+                format!("{}: {:.0}%", name, volume.volume(*bus) * 100.)
+            }
+        }
+    }
+
+    fn toggle(&self, accessibility: &mut Accessibility) {
+        match self {
+            Setting::Colorblind => accessibility.colorblind = !accessibility.colorblind,
+            Setting::HighContrast => accessibility.high_contrast = !accessibility.high_contrast,
+            Setting::ReducedMotion => accessibility.reduced_motion = !accessibility.reduced_motion,
+            Setting::GameSpeed | Setting::Volume(_) => {}
+            Setting::Assist => accessibility.assist = !accessibility.assist,
+        }
+    }
+}
+
 #[derive(Component)]
 enum MenuButtonAction {
     Play,
+    Survival,
+    Panic,
+    TimeAttack,
+    PlayLevel(usize),
     Scores,
+    Replay,
+    Editor,
+    Settings,
+    Toggle(Setting),
+    Slower,
+    Faster,
+    Quieter(VolumeBus),
+    Louder(VolumeBus),
     BackToMain,
     Quit,
 }
@@ -51,29 +151,80 @@ enum MenuButtonAction {
 #[derive(Component)]
 struct SelectedOption;
 
-fn setup_system(mut menu_state: ResMut<State<MenuState>>) {
-    menu_state.set(MenuState::Main).unwrap();
+// A level played ends back at the levels, with its results on them.
+fn setup_system(mut menu_state: ResMut<State<MenuState>>, game_mode: Res<GameMode>) {
+    menu_state
+        .set(if game_mode.ends_when_cleared() {
+            MenuState::LevelSelect
+        } else {
+            MenuState::Main
+        })
+        .unwrap();
+}
+
+// Colour of a button that is neither hovered nor pressed.
+fn normal_button_color(accessibility: &Accessibility) -> Color {
+    if accessibility.high_contrast {
+        HIGH_CONTRAST_NORMAL_BUTTON
+    } else {
+        NORMAL_BUTTON
+    }
+}
+
+fn medal_color(medal: Medal) -> Color {
+    match medal {
+        Medal::Gold => GOLD_COLOR,
+        Medal::Silver => SILVER_COLOR,
+        Medal::Bronze => BRONZE_COLOR,
+    }
 }
 
 // This is intended.
 #[allow(clippy::type_complexity)]
 fn button_system(
+    accessibility: Res<Accessibility>,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
-        (Changed<Interaction>, With<Button>),
+        (
+            &Interaction,
+            ChangeTrackers<Interaction>,
+            &mut BackgroundColor,
+            Option<&SelectedOption>,
+        ),
+        With<Button>,
     >,
 ) {
-    for (interaction, mut color, selected) in &mut interaction_query {
+    let (normal, hovered, hovered_pressed, pressed) = if accessibility.high_contrast {
+        (
+            HIGH_CONTRAST_NORMAL_BUTTON,
+            HIGH_CONTRAST_HOVERED_BUTTON,
+            HIGH_CONTRAST_HOVERED_PRESSED_BUTTON,
+            HIGH_CONTRAST_PRESSED_BUTTON,
+        )
+    } else {
+        (
+            NORMAL_BUTTON,
+            HOVERED_BUTTON,
+            HOVERED_PRESSED_BUTTON,
+            PRESSED_BUTTON,
+        )
+    };
+
+    // Every button is recoloured when the high-contrast setting changes.
+    for (interaction, interaction_tracker, mut color, selected) in &mut interaction_query {
+        if !interaction_tracker.is_changed() && !accessibility.is_changed() {
+            continue;
+        }
         *color = match (*interaction, selected) {
-            (Interaction::Clicked, _) | (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
-            (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
-            (Interaction::Hovered, None) => HOVERED_BUTTON.into(),
-            (Interaction::None, None) => NORMAL_BUTTON.into(),
+            (Interaction::Clicked, _) | (Interaction::None, Some(_)) => pressed.into(),
+            (Interaction::Hovered, Some(_)) => hovered_pressed.into(),
+            (Interaction::Hovered, None) => hovered.into(),
+            (Interaction::None, None) => normal.into(),
         }
     }
 }
 
-fn main_system(mut commands: Commands, fonts: Res<Fonts>) {
+fn main_system(mut commands: Commands, fonts: Res<Fonts>, accessibility: Res<Accessibility>) {
+    let normal_button = normal_button_color(&accessibility);
     let font = fonts.default.clone();
     let button_style = Style {
         size: Size::new(Val::Px(BUTTON_SIZE_PX.0), Val::Px(BUTTON_SIZE_PX.1)),
@@ -115,57 +266,56 @@ fn main_system(mut commands: Commands, fonts: Res<Fonts>) {
                     ..default()
                 }),
             );
+            // Two buttons to a row, so they all fit on the screen.
             parent
-                .spawn((
-                    ButtonBundle {
-                        style: button_style.clone(),
-                        background_color: NORMAL_BUTTON.into(),
-                        ..default()
-                    },
-                    MenuButtonAction::Play,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        TEXT_PLAY_BUTTON.to_string(),
-                        button_text_style.clone(),
-                    ));
-                });
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: button_style.clone(),
-                        background_color: NORMAL_BUTTON.into(),
-                        ..default()
-                    },
-                    MenuButtonAction::Scores,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        TEXT_SCORES_BUTTON.to_string(),
-                        button_text_style.clone(),
-                    ));
-                });
-            parent
-                .spawn((
-                    ButtonBundle {
-                        style: button_style,
-                        background_color: NORMAL_BUTTON.into(),
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(
+                            Val::Px(MENU_COLUMNS * (BUTTON_SIZE_PX.0 + 2. * BUTTON_MARGIN_PX)),
+                            Val::Auto,
+                        ),
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
                         ..default()
                     },
-                    MenuButtonAction::Quit,
-                ))
+                    ..default()
+                })
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        TEXT_QUIT_BUTTON.to_string(),
-                        button_text_style,
-                    ));
+                    for (action, text) in [
+                        (MenuButtonAction::Play, TEXT_PLAY_BUTTON),
+                        (MenuButtonAction::TimeAttack, TEXT_TIME_ATTACK_BUTTON),
+                        (MenuButtonAction::Survival, TEXT_SURVIVAL_BUTTON),
+                        (MenuButtonAction::Panic, TEXT_PANIC_BUTTON),
+                        (MenuButtonAction::Scores, TEXT_SCORES_BUTTON),
+                        (MenuButtonAction::Replay, TEXT_REPLAY_BUTTON),
+                        (MenuButtonAction::Editor, TEXT_EDITOR_BUTTON),
+                        (MenuButtonAction::Settings, TEXT_SETTINGS_BUTTON),
+                        (MenuButtonAction::Quit, TEXT_QUIT_BUTTON),
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: normal_button.into(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    text.to_string(),
+                                    button_text_style.clone(),
+                                ));
+                            });
+                    }
                 });
         });
 }
 
 // This is intended.
-#[allow(clippy::type_complexity)]
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn action_system(
+    mut commands: Commands,
     interaction_query: Query<
         (&Interaction, &MenuButtonAction),
         (Changed<Interaction>, With<Button>),
@@ -173,23 +323,77 @@ fn action_system(
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<State<MenuState>>,
     mut game_state: ResMut<State<AppState>>,
+    mut game_mode: ResMut<GameMode>,
+    mut level_handles: ResMut<LevelHandles>,
+    asset_server: Res<AssetServer>,
+    mut accessibility: ResMut<Accessibility>,
+    mut volume: ResMut<VolumeSettings>,
+    mut clear_color: ResMut<ClearColor>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Clicked {
             match menu_button_action {
                 MenuButtonAction::Quit => app_exit_events.send(AppExit),
                 MenuButtonAction::Play => {
+                    *game_mode = GameMode::Campaign;
+                    menu_state.set(MenuState::LevelSelect).unwrap();
+                }
+                MenuButtonAction::Survival => {
+                    *game_mode = GameMode::Survival;
+                    game_state.set(AppState::Game).unwrap();
+                    menu_state.set(MenuState::Disabled).unwrap();
+                }
+                MenuButtonAction::Panic => {
+                    *game_mode = GameMode::Panic;
+                    game_state.set(AppState::Game).unwrap();
+                    menu_state.set(MenuState::Disabled).unwrap();
+                }
+                MenuButtonAction::TimeAttack => {
+                    *game_mode = GameMode::TimeAttack;
+                    menu_state.set(MenuState::LevelSelect).unwrap();
+                }
+                MenuButtonAction::PlayLevel(index) => {
+                    level_handles.selected = *index;
                     game_state.set(AppState::Game).unwrap();
                     menu_state.set(MenuState::Disabled).unwrap();
                 }
                 MenuButtonAction::Scores => menu_state.set(MenuState::Scores).unwrap(),
+                // Watches the most recent run, if there is one.
+                MenuButtonAction::Replay => {
+                    if let Some(replay) = Replay::latest() {
+                        *game_mode = replay.mode;
+                        level_handles.select(&asset_server, &replay.level);
+                        commands.insert_resource(ReplayPlayback::new(replay));
+                        game_state.set(AppState::Game).unwrap();
+                        menu_state.set(MenuState::Disabled).unwrap();
+                    }
+                }
+                MenuButtonAction::Editor => {
+                    game_state.set(AppState::Editor).unwrap();
+                    menu_state.set(MenuState::Disabled).unwrap();
+                }
+                MenuButtonAction::Settings => menu_state.set(MenuState::Settings).unwrap(),
+                MenuButtonAction::Toggle(setting) => {
+                    setting.toggle(&mut accessibility);
+                    clear_color.0 = accessibility.background_color();
+                }
+                MenuButtonAction::Slower => accessibility.slower(),
+                MenuButtonAction::Faster => accessibility.faster(),
+                MenuButtonAction::Quieter(bus) => volume.quieter(*bus),
+                MenuButtonAction::Louder(bus) => volume.louder(*bus),
                 MenuButtonAction::BackToMain => menu_state.set(MenuState::Main).unwrap(),
             }
         }
     }
 }
 
-fn score_system(mut commands: Commands, fonts: Res<Fonts>, mut scores: ResMut<Scores>) {
+fn score_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    scores: Res<Scores>,
+    accessibility: Res<Accessibility>,
+) {
+    let normal_button = normal_button_color(&accessibility);
     let font = fonts.default.clone();
     let score_text_style = TextStyle {
         font: fonts.default.clone(),
@@ -203,6 +407,11 @@ fn score_system(mut commands: Commands, fonts: Res<Fonts>, mut scores: ResMut<Sc
         align_items: AlignItems::Center,
         ..default()
     };
+    let mode_text_style = TextStyle {
+        font: fonts.default.clone(),
+        font_size: TEXT_MODE_SIZE,
+        color: TEXT_COLOR,
+    };
     let button_text_style = TextStyle {
         font,
         font_size: TEXT_BUTTON_SIZE,
@@ -222,36 +431,56 @@ fn score_system(mut commands: Commands, fonts: Res<Fonts>, mut scores: ResMut<Sc
             ScoresScreen,
         ))
         .with_children(|parent| {
-            if !scores.score_list.is_empty() {
-                parent.spawn(TextBundle::from_section(
-                    TEXT_SCORES.to_string(),
-                    score_text_style.clone(),
-                ));
-            } else {
-                parent.spawn(TextBundle::from_section(
-                    TEXT_NO_SCORES.to_string(),
-                    score_text_style.clone(),
-                ));
-            }
-            scores.score_list.sort();
-            for position in 0..5 {
-                if scores.score_list.len() > position {
-                    if let Some(score) = scores
-                        .score_list
-                        .get(scores.score_list.len() - position - 1)
-                    {
-                        parent.spawn(TextBundle::from_section(
-                            format!("{}.    {}", position + 1, score),
-                            score_text_style.clone(),
-                        ));
+            parent.spawn(TextBundle::from_section(
+                TEXT_SCORES.to_string(),
+                score_text_style.clone(),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for game_mode in GameMode::SCORED {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Px(MENU_MARGIN_PX)),
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    game_mode.name().to_string(),
+                                    mode_text_style.clone(),
+                                ));
+                                let top_scores = scores.top(game_mode, TOP_SCORES_COUNT);
+                                if top_scores.is_empty() {
+                                    parent.spawn(TextBundle::from_section(
+                                        TEXT_NO_SCORES.to_string(),
+                                        mode_text_style.clone(),
+                                    ));
+                                }
+                                for (position, score) in top_scores.iter().enumerate() {
+                                    parent.spawn(TextBundle::from_section(
+                                        format!("{}.    {}", position + 1, score),
+                                        score_text_style.clone(),
+                                    ));
+                                }
+                            });
                     }
-                }
-            }
+                });
             parent
                 .spawn((
                     ButtonBundle {
                         style: button_style,
-                        background_color: NORMAL_BUTTON.into(),
+                        background_color: normal_button.into(),
                         ..default()
                     },
                     MenuButtonAction::BackToMain,
@@ -265,6 +494,374 @@ fn score_system(mut commands: Commands, fonts: Res<Fonts>, mut scores: ResMut<Sc
         });
 }
 
+// Toggles on the left, settings changed in steps on the right.
+fn settings_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    accessibility: Res<Accessibility>,
+    volume: Res<VolumeSettings>,
+) {
+    let normal_button = normal_button_color(&accessibility);
+    let title_text_style = TextStyle {
+        font: fonts.default.clone(),
+        font_size: TEXT_TITLE_SIZE,
+        color: TEXT_COLOR,
+    };
+    let button_text_style = TextStyle {
+        font: fonts.default.clone(),
+        font_size: TEXT_BUTTON_SIZE,
+        color: TEXT_COLOR,
+    };
+    let setting_button_style = Style {
+        size: Size::new(
+            Val::Px(SETTING_BUTTON_SIZE_PX.0),
+            Val::Px(SETTING_BUTTON_SIZE_PX.1),
+        ),
+        margin: UiRect::all(Val::Px(BUTTON_MARGIN_PX)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let step_button_style = Style {
+        size: Size::new(
+            Val::Px(STEP_BUTTON_SIZE_PX.0),
+            Val::Px(STEP_BUTTON_SIZE_PX.1),
+        ),
+        margin: UiRect::all(Val::Px(BUTTON_MARGIN_PX)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_style = Style {
+        size: Size::new(Val::Px(BUTTON_SIZE_PX.0), Val::Px(BUTTON_SIZE_PX.1)),
+        margin: UiRect::all(Val::Px(BUTTON_MARGIN_PX)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let column_style = Style {
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    // The setting's value between a button lowering it and one raising it.
+    let spawn_stepper = |parent: &mut ChildBuilder,
+                         setting: Setting,
+                         less: MenuButtonAction,
+                         more: MenuButtonAction| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: step_button_style.clone(),
+                            background_color: normal_button.into(),
+                            ..default()
+                        },
+                        less,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            TEXT_LESS_BUTTON,
+                            button_text_style.clone(),
+                        ));
+                    });
+                parent.spawn((
+                    TextBundle::from_section(
+                        setting.label(&accessibility, &volume),
+                        button_text_style.clone(),
+                    ),
+                    setting,
+                ));
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: step_button_style.clone(),
+                            background_color: normal_button.into(),
+                            ..default()
+                        },
+                        more,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            TEXT_MORE_BUTTON,
+                            button_text_style.clone(),
+                        ));
+                    });
+            });
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Auto),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(TEXT_SETTINGS, title_text_style));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: column_style.clone(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for setting in [
+                                Setting::Colorblind,
+                                Setting::HighContrast,
+                                Setting::ReducedMotion,
+                                Setting::Assist,
+                            ] {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: setting_button_style.clone(),
+                                            background_color: normal_button.into(),
+                                            ..default()
+                                        },
+                                        MenuButtonAction::Toggle(setting),
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            TextBundle::from_section(
+                                                setting.label(&accessibility, &volume),
+                                                button_text_style.clone(),
+                                            ),
+                                            setting,
+                                        ));
+                                    });
+                            }
+                        });
+                    parent
+                        .spawn(NodeBundle {
+                            style: column_style,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            spawn_stepper(
+                                parent,
+                                Setting::GameSpeed,
+                                MenuButtonAction::Slower,
+                                MenuButtonAction::Faster,
+                            );
+                            for bus in [VolumeBus::Master, VolumeBus::Music, VolumeBus::Effects] {
+                                spawn_stepper(
+                                    parent,
+                                    Setting::Volume(bus),
+                                    MenuButtonAction::Quieter(bus),
+                                    MenuButtonAction::Louder(bus),
+                                );
+                            }
+                        });
+                });
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: button_style,
+                        background_color: normal_button.into(),
+                        ..default()
+                    },
+                    MenuButtonAction::BackToMain,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        TEXT_BACK_MENU,
+                        button_text_style.clone(),
+                    ));
+                });
+        });
+}
+
+// Every level with its best results, the campaign's medals are stars. A campaign level is
+// locked until the one before it is cleared.
+// This is intended.
+#[allow(clippy::too_many_arguments)]
+fn level_select_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    asset_server: Res<AssetServer>,
+    levels: Res<Assets<Level>>,
+    level_handles: Res<LevelHandles>,
+    records: Res<Records>,
+    game_mode: Res<GameMode>,
+    accessibility: Res<Accessibility>,
+) {
+    let normal_button = normal_button_color(&accessibility);
+    let title_text_style = TextStyle {
+        font: fonts.default.clone(),
+        font_size: TEXT_TITLE_SIZE,
+        color: TEXT_COLOR,
+    };
+    let button_text_style = TextStyle {
+        font: fonts.default.clone(),
+        font_size: TEXT_BUTTON_SIZE,
+        color: TEXT_COLOR,
+    };
+    let level_button_style = Style {
+        size: Size::new(
+            Val::Px(LEVEL_BUTTON_SIZE_PX.0),
+            Val::Px(LEVEL_BUTTON_SIZE_PX.1),
+        ),
+        margin: UiRect::all(Val::Px(BUTTON_MARGIN_PX)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_style = Style {
+        size: Size::new(Val::Px(BUTTON_SIZE_PX.0), Val::Px(BUTTON_SIZE_PX.1)),
+        margin: UiRect::all(Val::Px(BUTTON_MARGIN_PX)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Auto),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LevelSelectScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(game_mode.name(), title_text_style));
+            let path = |index| level_handles.level_path(&asset_server, index);
+            for (index, handle) in level_handles.levels.iter().enumerate() {
+                // Time attack can be played on any level.
+                let locked = *game_mode != GameMode::TimeAttack
+                    && index > 0
+                    && records.level(&path(index - 1)).is_none();
+                let sections = if locked {
+                    vec![TextSection::new(
+                        format!("Level {}    {}", index + 1, TEXT_LOCKED),
+                        TextStyle {
+                            color: LOCKED_TEXT_COLOR,
+                            ..button_text_style.clone()
+                        },
+                    )]
+                } else if *game_mode == GameMode::TimeAttack {
+                    let best_time = records.best_time(&path(index));
+                    let medal = levels
+                        .get(handle)
+                        .and_then(|level| level.medals)
+                        .zip(best_time)
+                        .and_then(|(medals, best_time)| medals.medal(best_time));
+                    vec![
+                        TextSection::new(
+                            format!(
+                                "Level {}    {}    ",
+                                index + 1,
+                                best_time.map_or(TEXT_NO_TIME.to_string(), format_time)
+                            ),
+                            button_text_style.clone(),
+                        ),
+                        TextSection::new(
+                            medal.map_or("", |medal| medal.name()),
+                            TextStyle {
+                                color: medal.map_or(TEXT_COLOR, medal_color),
+                                ..button_text_style.clone()
+                            },
+                        ),
+                    ]
+                } else {
+                    let record = records.level(&path(index));
+                    let stars = record.map_or(0, |record| record.stars as usize);
+                    vec![
+                        TextSection::new(
+                            format!(
+                                "Level {}    {}    {}    ",
+                                index + 1,
+                                record.map_or(0, |record| record.score),
+                                record.map_or(TEXT_NO_TIME.to_string(), |record| {
+                                    format_time(record.time)
+                                })
+                            ),
+                            button_text_style.clone(),
+                        ),
+                        TextSection::new(
+                            TEXT_STAR.repeat(stars),
+                            TextStyle {
+                                color: GOLD_COLOR,
+                                ..button_text_style.clone()
+                            },
+                        ),
+                        TextSection::new(
+                            TEXT_STAR.repeat(MAX_STARS - stars),
+                            TextStyle {
+                                color: LOCKED_TEXT_COLOR,
+                                ..button_text_style.clone()
+                            },
+                        ),
+                    ]
+                };
+                let mut level_button = parent.spawn(ButtonBundle {
+                    style: level_button_style.clone(),
+                    background_color: normal_button.into(),
+                    ..default()
+                });
+                level_button.with_children(|parent| {
+                    parent.spawn(TextBundle::from_sections(sections));
+                });
+                if !locked {
+                    level_button.insert(MenuButtonAction::PlayLevel(index));
+                }
+            }
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: button_style,
+                        background_color: normal_button.into(),
+                        ..default()
+                    },
+                    MenuButtonAction::BackToMain,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(TEXT_BACK_MENU, button_text_style));
+                });
+        });
+}
+
+fn setting_text_system(
+    accessibility: Res<Accessibility>,
+    volume: Res<VolumeSettings>,
+    mut text_query: Query<(&mut Text, &Setting)>,
+) {
+    if accessibility.is_changed() || volume.is_changed() {
+        for (mut text, setting) in &mut text_query {
+            text.sections[0].value = setting.label(&accessibility, &volume);
+        }
+    }
+}
+
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_state(MenuState::Disabled)
@@ -277,10 +874,23 @@ impl Plugin for MenuPlugin {
                 SystemSet::on_exit(MenuState::Scores).with_system(despawn_screen::<ScoresScreen>),
             )
             .add_system_set(SystemSet::on_enter(MenuState::Scores).with_system(score_system))
+            .add_system_set(SystemSet::on_enter(MenuState::Settings).with_system(settings_system))
+            .add_system_set(
+                SystemSet::on_enter(MenuState::LevelSelect).with_system(level_select_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(MenuState::LevelSelect)
+                    .with_system(despawn_screen::<LevelSelectScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_exit(MenuState::Settings)
+                    .with_system(despawn_screen::<SettingsScreen>),
+            )
             .add_system_set(
                 SystemSet::on_update(AppState::Menu)
                     .with_system(action_system)
-                    .with_system(button_system),
+                    .with_system(button_system)
+                    .with_system(setting_text_system),
             );
     }
 }
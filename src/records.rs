@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+pub struct RecordsPlugin;
+
+const RECORDS_FILE: &str = "records.ron";
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Records::load())
+            .add_system(save_records_system);
+    }
+}
+
+// Personal bests kept in `RECORDS_FILE` between runs, levels are named by their asset path.
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Records {
+    // Quickest time attack clear of every level, in milliseconds.
+    best_times: BTreeMap<String, u64>,
+    // Campaign levels cleared so far, with the best of their runs.
+    campaign: BTreeMap<String, LevelRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct LevelRecord {
+    pub score: usize,
+    // Milliseconds.
+    pub time: u64,
+    pub stars: u8,
+}
+
+impl Records {
+    fn load() -> Self {
+        let records = match fs::read_to_string(RECORDS_FILE) {
+            Ok(records) => records,
+            Err(_) => return Self::default(),
+        };
+        match ron::from_str::<Self>(&records) {
+            Ok(records) => records,
+            Err(error) => {
+                warn!("Ignoring invalid {}: {}", RECORDS_FILE, error);
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let records = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(records) => records,
+            Err(error) => {
+                warn!("Could not serialize the records: {}", error);
+                return;
+            }
+        };
+        if let Err(error) = fs::write(RECORDS_FILE, records) {
+            warn!("Could not save {}: {}", RECORDS_FILE, error);
+        }
+    }
+
+    pub fn best_time(&self, level: &str) -> Option<u64> {
+        self.best_times.get(level).copied()
+    }
+
+    // Keeps the time if it beats the best one.
+    pub fn finish(&mut self, level: String, millis: u64) {
+        let best_time = self.best_times.entry(level).or_insert(millis);
+        *best_time = (*best_time).min(millis);
+    }
+
+    pub fn level(&self, level: &str) -> Option<LevelRecord> {
+        self.campaign.get(level).copied()
+    }
+
+    // Each best is kept on its own, the best score may come from a slower run.
+    pub fn clear(&mut self, level: String, record: LevelRecord) {
+        let best = self.campaign.entry(level).or_insert(record);
+        best.score = best.score.max(record.score);
+        best.time = best.time.min(record.time);
+        best.stars = best.stars.max(record.stars);
+    }
+}
+
+// Minutes, seconds and milliseconds, like 1:05.250.
+pub fn format_time(millis: u64) -> String {
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+fn save_records_system(records: Res<Records>) {
+    if records.is_changed() && !records.is_added() {
+        records.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finish_keeps_the_quickest_time() {
+        let mut records = Records::default();
+        assert_eq!(records.best_time("levels/1.level.ron"), None);
+        for millis in [30_000, 25_500, 41_000] {
+            records.finish("levels/1.level.ron".to_string(), millis);
+        }
+        assert_eq!(records.best_time("levels/1.level.ron"), Some(25_500));
+        assert_eq!(records.best_time("levels/2.level.ron"), None);
+    }
+
+    #[test]
+    fn clear_keeps_each_best_on_its_own() {
+        let mut records = Records::default();
+        let level = "levels/1.level.ron".to_string();
+        records.clear(
+            level.clone(),
+            LevelRecord {
+                score: 5000,
+                time: 40_000,
+                stars: 2,
+            },
+        );
+        records.clear(
+            level.clone(),
+            LevelRecord {
+                score: 3000,
+                time: 35_000,
+                stars: 1,
+            },
+        );
+        let best = records.level(&level).unwrap();
+        assert_eq!(best.score, 5000);
+        assert_eq!(best.time, 35_000);
+        assert_eq!(best.stars, 2);
+        assert!(records.level("levels/2.level.ron").is_none());
+    }
+
+    #[test]
+    fn times_are_formatted_in_minutes() {
+        assert_eq!(format_time(65_250), "1:05.250");
+        assert_eq!(format_time(999), "0:00.999");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::audio::VolumeSettings;
+use crate::BACKGROUND_COLOR;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+pub struct SettingsPlugin;
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+const GAME_SPEED_MIN: u32 = 50;
+const GAME_SPEED_MAX: u32 = 100;
+const GAME_SPEED_STEP: u32 = 10;
+
+const HIGH_CONTRAST_BACKGROUND_COLOR: Color = Color::BLACK;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = Settings::load();
+        app.insert_resource(settings.accessibility)
+            .insert_resource(settings.volume)
+            .add_system(save_settings_system);
+    }
+}
+
+// Kept in `SETTINGS_FILE` between runs, anything missing from it keeps its default.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Settings {
+    accessibility: Accessibility,
+    volume: VolumeSettings,
+}
+
+impl Settings {
+    fn load() -> Self {
+        let settings = match fs::read_to_string(SETTINGS_FILE) {
+            Ok(settings) => settings,
+            Err(_) => return Self::default(),
+        };
+        match ron::from_str::<Self>(&settings) {
+            Ok(mut settings) => {
+                settings.accessibility.game_speed = settings
+                    .accessibility
+                    .game_speed
+                    .clamp(GAME_SPEED_MIN, GAME_SPEED_MAX);
+                settings.volume.clamp();
+                settings
+            }
+            Err(error) => {
+                warn!("Ignoring invalid {}: {}", SETTINGS_FILE, error);
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let settings = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(settings) => settings,
+            Err(error) => {
+                warn!("Could not serialize the settings: {}", error);
+                return;
+            }
+        };
+        if let Err(error) = fs::write(SETTINGS_FILE, settings) {
+            warn!("Could not save {}: {}", SETTINGS_FILE, error);
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(default)]
+pub struct Accessibility {
+    pub colorblind: bool,
+    pub high_contrast: bool,
+    // Turns off the screen shake, the hit-stop, the zoom and the particles.
+    pub reduced_motion: bool,
+    // Percent of the normal speed the game runs at.
+    pub game_speed: u32,
+    // Extra lives for an easier game.
+    pub assist: bool,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self {
+            colorblind: false,
+            high_contrast: false,
+            reduced_motion: false,
+            game_speed: GAME_SPEED_MAX,
+            assist: false,
+        }
+    }
+}
+
+impl Accessibility {
+    // Speed as a fraction of the normal one.
+    pub fn speed(&self) -> f32 {
+        self.game_speed as f32 / 100.
+    }
+
+    pub fn slower(&mut self) {
+        self.game_speed = self
+            .game_speed
+            .saturating_sub(GAME_SPEED_STEP)
+            .max(GAME_SPEED_MIN);
+    }
+
+    pub fn faster(&mut self) {
+        self.game_speed = (self.game_speed + GAME_SPEED_STEP).min(GAME_SPEED_MAX);
+    }
+
+    // Clear colour of the screens outside the game, the game takes its own from the theme.
+    pub fn background_color(&self) -> Color {
+        if self.high_contrast {
+            HIGH_CONTRAST_BACKGROUND_COLOR
+        } else {
+            BACKGROUND_COLOR
+        }
+    }
+}
+
+fn save_settings_system(accessibility: Res<Accessibility>, volume: Res<VolumeSettings>) {
+    let accessibility_changed = accessibility.is_changed() && !accessibility.is_added();
+    let volume_changed = volume.is_changed() && !volume.is_added();
+    if accessibility_changed || volume_changed {
+        Settings {
+            accessibility: accessibility.clone(),
+            volume: volume.clone(),
+        }
+        .save();
+    }
+}
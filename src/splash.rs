@@ -1,3 +1,4 @@
+use crate::settings::Accessibility;
 use crate::{despawn_screen, AppState};
 use bevy::prelude::*;
 
@@ -24,8 +25,14 @@ impl Plugin for SplashPlugin {
     }
 }
 
-fn splash_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn splash_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    accessibility: Res<Accessibility>,
+    mut clear_color: ResMut<ClearColor>,
+) {
     let logo = asset_server.load(LOGO_FILE);
+    clear_color.0 = accessibility.background_color();
 
     commands.spawn((
         ImageBundle {